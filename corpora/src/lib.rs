@@ -0,0 +1,8 @@
+/// English prose from classic novels
+pub const ENGLISH: &str = include_str!("../data/english.txt");
+
+/// Homeric Greek, from the openings of the Odyssey and the Iliad
+pub const GREEK: &str = include_str!("../data/greek.txt");
+
+/// Russian verse and prose from Pushkin and Dostoevsky
+pub const RUSSIAN: &str = include_str!("../data/russian.txt");
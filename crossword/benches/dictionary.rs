@@ -0,0 +1,61 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use crossword::dictionary::Dictionary;
+use crossword::solver::Comparison;
+use std::hint::black_box;
+
+const LETTERS: &[char] = &[
+    'a', 'b', 'c', 'd', 'e', 'é', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm',
+];
+
+/// deterministic pseudo-random words of 4 to 12 letters, plus a fixed handful containing `z` so
+/// that the number of matches for a `z` pattern stays the same as the dictionary grows
+fn words(count: usize) -> Vec<String> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        usize::try_from(state >> 32).unwrap()
+    };
+    let mut words: Vec<String> = (0..count)
+        .map(|_| {
+            let length = 4 + next() % 9;
+            (0..length)
+                .map(|_| LETTERS[next() % LETTERS.len()])
+                .collect()
+        })
+        .collect();
+    for i in 0..10 {
+        words[i * count / 10] = format!("{}bzcdefg", LETTERS[i]);
+    }
+    words
+}
+
+fn lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lookup");
+    let pattern = [None, None, Some('z'), None, None, None, None, None];
+    for count in [1_000, 10_000, 100_000, 1_000_000] {
+        let words = words(count);
+        let dictionary = Dictionary::new(&words, Comparison::IgnoreAccents);
+        group.bench_with_input(BenchmarkId::new("indexed", count), &dictionary, |b, d| {
+            b.iter(|| d.matches(black_box(&pattern)));
+        });
+        group.bench_with_input(BenchmarkId::new("linear", count), &words, |b, words| {
+            b.iter(|| {
+                words
+                    .iter()
+                    .filter(|word| {
+                        word.chars().count() == pattern.len()
+                            && word.chars().zip(black_box(&pattern)).all(|(c, p)| {
+                                p.is_none_or(|p| Comparison::IgnoreAccents.fold(c) == p)
+                            })
+                    })
+                    .count()
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, lookup);
+criterion_main!(benches);
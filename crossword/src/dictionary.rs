@@ -0,0 +1,139 @@
+use crate::solver::Comparison;
+use std::collections::HashMap;
+
+/// a word list indexed by (length, position, letter) so that pattern lookups only touch words
+/// sharing the rarest known letter of the pattern
+pub struct Dictionary {
+    folded: Vec<Vec<char>>,
+    by_length: HashMap<usize, Vec<usize>>,
+    by_letter: HashMap<(usize, usize, char), Vec<usize>>,
+    comparison: Comparison,
+}
+
+impl Dictionary {
+    pub fn new(words: &[String], comparison: Comparison) -> Self {
+        let folded: Vec<Vec<char>> = words
+            .iter()
+            .map(|word| comparison.fold_word(word))
+            .collect();
+        let mut by_length = HashMap::new();
+        let mut by_letter = HashMap::new();
+        for (i, letters) in folded.iter().enumerate() {
+            by_length.entry(letters.len()).or_insert(Vec::new()).push(i);
+            for (position, &letter) in letters.iter().enumerate() {
+                by_letter
+                    .entry((letters.len(), position, letter))
+                    .or_insert(Vec::new())
+                    .push(i);
+            }
+        }
+        Self {
+            folded,
+            by_length,
+            by_letter,
+            comparison,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.folded.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.folded.is_empty()
+    }
+
+    pub fn comparison(&self) -> Comparison {
+        self.comparison
+    }
+
+    /// the word at `index` as it is compared, i.e. NFC-normalised and folded according to the
+    /// comparison level
+    pub fn folded(&self, index: usize) -> &[char] {
+        &self.folded[index]
+    }
+
+    /// indices of every word matching `pattern`, in dictionary order. `None` matches any letter
+    /// and known letters are normalised and folded before lookup
+    pub fn matches(&self, pattern: &[Option<char>]) -> Vec<usize> {
+        let length = pattern.len();
+        let known: Vec<(usize, char)> = pattern
+            .iter()
+            .enumerate()
+            .filter_map(|(position, c)| Some((position, self.comparison.fold((*c)?))))
+            .collect();
+        let mut postings = Vec::new();
+        for &(position, letter) in &known {
+            match self.by_letter.get(&(length, position, letter)) {
+                Some(words) => postings.push(words),
+                None => return Vec::new(),
+            }
+        }
+        let Some(shortest) = postings
+            .into_iter()
+            .min_by_key(|words| words.len())
+            .or_else(|| self.by_length.get(&length))
+        else {
+            return Vec::new();
+        };
+        shortest
+            .iter()
+            .copied()
+            .filter(|&i| {
+                known
+                    .iter()
+                    .all(|&(position, letter)| self.folded[i][position] == letter)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dictionary(comparison: Comparison) -> Dictionary {
+        let words: Vec<String> = ["abcd", "abce", "xbce", "Àbc", "abc"]
+            .iter()
+            .map(|&word| word.to_owned())
+            .collect();
+        Dictionary::new(&words, comparison)
+    }
+
+    #[test]
+    fn test_matches() {
+        let dictionary = dictionary(Comparison::Exact);
+        assert_eq!(dictionary.matches(&[None; 4]), [0, 1, 2]);
+        assert_eq!(
+            dictionary.matches(&[None, None, Some('c'), None]),
+            [0, 1, 2]
+        );
+        assert_eq!(dictionary.matches(&[None, None, None, Some('e')]), [1, 2]);
+        assert_eq!(dictionary.matches(&[Some('a'), None, None, Some('e')]), [1]);
+        assert_eq!(
+            dictionary.matches(&[Some('z'), None, None, None]),
+            [] as [usize; 0]
+        );
+        assert_eq!(dictionary.matches(&[None; 5]), [] as [usize; 0]);
+    }
+
+    #[test]
+    fn test_matches_folded() {
+        let dictionary = dictionary(Comparison::IgnoreAccents);
+        assert_eq!(dictionary.matches(&[Some('a'), None, None]), [3, 4]);
+        assert_eq!(dictionary.matches(&[Some('Á'), None, None]), [3, 4]);
+        assert_eq!(dictionary.folded(3), ['a', 'b', 'c']);
+    }
+
+    #[test]
+    fn test_matches_decomposed() {
+        let words = ["cafe\u{301}".to_owned(), "cafe".to_owned()];
+        let exact = Dictionary::new(&words, Comparison::Exact);
+        assert_eq!(exact.matches(&[None; 4]), [0, 1]);
+        assert_eq!(exact.matches(&[None, None, None, Some('é')]), [0]);
+        assert_eq!(exact.folded(0), ['c', 'a', 'f', 'é']);
+        let folded = Dictionary::new(&words, Comparison::IgnoreAccents);
+        assert_eq!(folded.matches(&[None, None, None, Some('e')]), [0, 1]);
+        assert_eq!(folded.matches(&[None, None, None, Some('É')]), [0, 1]);
+    }
+}
@@ -0,0 +1,2 @@
+pub mod dictionary;
+pub mod solver;
@@ -0,0 +1,363 @@
+use crate::dictionary::Dictionary;
+use std::collections::HashMap;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+const BLANK: char = '.';
+const BLOCK: char = '#';
+
+/// how strictly a dictionary letter has to agree with a letter in the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Exact,
+    IgnoreCase,
+    /// ignores case and any combining marks, so `É` matches `e`
+    IgnoreAccents,
+}
+
+impl Comparison {
+    /// maps `c` to the form used when comparing it under this level, after NFC normalisation,
+    /// so that a singleton such as the Ångström sign compares as the letter `Å`
+    pub fn fold(self, c: char) -> char {
+        let c = std::iter::once(c).nfc().next().unwrap();
+        let c = match self {
+            Self::Exact => return c,
+            Self::IgnoreCase => c,
+            Self::IgnoreAccents => c.nfd().next().unwrap(),
+        };
+        c.to_lowercase().next().unwrap()
+    }
+
+    /// `word` as compared under this level: NFC-normalised, so that a decomposed letter is one
+    /// letter, then folded letter by letter. under `IgnoreAccents` any combining marks that have
+    /// no precomposed letter to join are dropped
+    pub fn fold_word(self, word: &str) -> Vec<char> {
+        word.nfc()
+            .filter(|&c| self != Self::IgnoreAccents || !is_combining_mark(c))
+            .map(|c| self.fold(c))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Across,
+    Down,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Slot {
+    pub row: usize,
+    pub col: usize,
+    pub direction: Direction,
+    cells: Vec<usize>,
+}
+
+impl Slot {
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+}
+
+#[derive(Debug)]
+pub struct Crossword {
+    /// the letter given in each cell, if any
+    cells: Vec<Option<char>>,
+    slots: Vec<Slot>,
+}
+
+impl Crossword {
+    /// treats every non-empty line as an independent across word, ignoring indentation
+    pub fn from_lines(input: &str) -> Self {
+        let mut cells = Vec::new();
+        let mut slots = Vec::new();
+        for (row, line) in input.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            let col = line.chars().take_while(|c| c.is_whitespace()).count();
+            let start = cells.len();
+            cells.extend(trimmed.nfc().map(|c| (c != BLANK).then_some(c)));
+            slots.push(Slot {
+                row,
+                col,
+                direction: Direction::Across,
+                cells: (start..cells.len()).collect(),
+            });
+        }
+        Self { cells, slots }
+    }
+
+    /// reads a 2D grid where spaces and `#` are blocks, `.` is a blank and anything else is a
+    /// given letter. every run of two or more cells across or down is a slot, so crossing slots
+    /// share cells
+    pub fn from_grid(input: &str) -> Self {
+        let mut cells = Vec::new();
+        let mut cell_at = HashMap::new();
+        let grid: Vec<Vec<char>> = input.lines().map(|line| line.chars().collect()).collect();
+        for (row, line) in grid.iter().enumerate() {
+            for (col, &c) in line.iter().enumerate() {
+                if !c.is_whitespace() && c != BLOCK {
+                    cell_at.insert((row, col), cells.len());
+                    cells.push((c != BLANK).then_some(c));
+                }
+            }
+        }
+        let mut slots = Vec::new();
+        let width = grid.iter().map(Vec::len).max().unwrap_or(0);
+        for (row, line) in grid.iter().enumerate() {
+            for col in 0..line.len() {
+                let run: Vec<usize> = (col..line.len())
+                    .map_while(|c| cell_at.get(&(row, c)).copied())
+                    .collect();
+                if run.len() >= 2 && (col == 0 || !cell_at.contains_key(&(row, col - 1))) {
+                    slots.push(Slot {
+                        row,
+                        col,
+                        direction: Direction::Across,
+                        cells: run,
+                    });
+                }
+            }
+        }
+        for col in 0..width {
+            for row in 0..grid.len() {
+                let run: Vec<usize> = (row..grid.len())
+                    .map_while(|r| cell_at.get(&(r, col)).copied())
+                    .collect();
+                if run.len() >= 2 && (row == 0 || !cell_at.contains_key(&(row - 1, col))) {
+                    slots.push(Slot {
+                        row,
+                        col,
+                        direction: Direction::Down,
+                        cells: run,
+                    });
+                }
+            }
+        }
+        Self { cells, slots }
+    }
+
+    pub fn slots(&self) -> &[Slot] {
+        &self.slots
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Solution {
+    /// the dictionary index filling each slot, or `None` for a slot whose letters were all given
+    pub words: Vec<Option<usize>>,
+    /// slots that some other complete solution fills with a different word
+    pub ambiguous: Vec<usize>,
+}
+
+pub struct Solver {
+    dictionary: Dictionary,
+}
+
+struct Search<'a> {
+    crossword: &'a Crossword,
+    cells: Vec<Option<char>>,
+    used: Vec<bool>,
+    words: Vec<Option<usize>>,
+    /// a (slot, word) pair that may not be part of the solution
+    banned: Option<(usize, usize)>,
+}
+
+impl Solver {
+    pub fn new(words: &[String], comparison: Comparison) -> Self {
+        Self {
+            dictionary: Dictionary::new(words, comparison),
+        }
+    }
+
+    /// fills every slot containing a blank with a distinct dictionary word, preferring words that
+    /// appear earlier in the dictionary
+    pub fn solve(&self, crossword: &Crossword) -> Option<Solution> {
+        let words = self.search(crossword, None)?;
+        let ambiguous = words
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, &word)| Some((slot, word?)))
+            .filter(|&banned| self.search(crossword, Some(banned)).is_some())
+            .map(|(slot, _)| slot)
+            .collect();
+        Some(Solution { words, ambiguous })
+    }
+
+    fn search(
+        &self,
+        crossword: &Crossword,
+        banned: Option<(usize, usize)>,
+    ) -> Option<Vec<Option<usize>>> {
+        let mut search = Search {
+            crossword,
+            cells: crossword
+                .cells
+                .iter()
+                .map(|c| c.map(|c| self.dictionary.comparison().fold(c)))
+                .collect(),
+            used: vec![false; self.dictionary.len()],
+            words: vec![None; crossword.slots.len()],
+            banned,
+        };
+        let open: Vec<usize> = (0..crossword.slots.len())
+            .filter(|&slot| {
+                crossword.slots[slot]
+                    .cells
+                    .iter()
+                    .any(|&cell| search.cells[cell].is_none())
+            })
+            .collect();
+        self.fill(&mut search, open).then_some(search.words)
+    }
+
+    fn candidates(&self, search: &Search, slot: usize) -> Vec<usize> {
+        let pattern: Vec<Option<char>> = search.crossword.slots[slot]
+            .cells
+            .iter()
+            .map(|&cell| search.cells[cell])
+            .collect();
+        self.dictionary
+            .matches(&pattern)
+            .into_iter()
+            .filter(|&word| !search.used[word] && search.banned != Some((slot, word)))
+            .collect()
+    }
+
+    /// backtracks over the `open` slots, always expanding the one with the fewest candidates
+    fn fill(&self, search: &mut Search, mut open: Vec<usize>) -> bool {
+        let Some((i, candidates)) = open
+            .iter()
+            .map(|&slot| self.candidates(search, slot))
+            .enumerate()
+            .min_by_key(|(_, candidates)| candidates.len())
+        else {
+            return true;
+        };
+        let slot = open.swap_remove(i);
+        for word in candidates {
+            let cells = &search.crossword.slots[slot].cells;
+            let filled: Vec<usize> = cells
+                .iter()
+                .copied()
+                .filter(|&cell| search.cells[cell].is_none())
+                .collect();
+            for (&cell, &letter) in cells.iter().zip(self.dictionary.folded(word)) {
+                search.cells[cell] = Some(letter);
+            }
+            search.used[word] = true;
+            search.words[slot] = Some(word);
+            if self.fill(search, open.clone()) {
+                return true;
+            }
+            search.words[slot] = None;
+            search.used[word] = false;
+            for cell in filled {
+                search.cells[cell] = None;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dictionary(words: &[&str]) -> Vec<String> {
+        words.iter().map(|&word| word.to_owned()).collect()
+    }
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(Comparison::Exact.fold('É'), 'É');
+        assert_eq!(Comparison::IgnoreCase.fold('É'), 'é');
+        assert_eq!(Comparison::IgnoreAccents.fold('É'), 'e');
+        assert_eq!(Comparison::IgnoreAccents.fold('ß'), 'ß');
+        assert_eq!(Comparison::Exact.fold('\u{212B}'), 'Å');
+        assert_eq!(
+            Comparison::Exact.fold_word("e\u{301}te\u{301}"),
+            ['é', 't', 'é']
+        );
+        assert_eq!(
+            Comparison::IgnoreAccents.fold_word("Q\u{303}e\u{301}"),
+            ['q', 'e']
+        );
+    }
+
+    #[test]
+    fn test_every_letter_is_honoured() {
+        let words = dictionary(&["abcd", "abce", "xbce"]);
+        let crossword = Crossword::from_lines("  ..c.\n x..e");
+        let solution = Solver::new(&words, Comparison::Exact)
+            .solve(&crossword)
+            .unwrap();
+        assert_eq!(solution.words, [Some(0), Some(2)]);
+        assert_eq!(solution.ambiguous, [0]);
+    }
+
+    #[test]
+    fn test_words_are_used_once() {
+        let words = dictionary(&["cat", "cot"]);
+        let crossword = Crossword::from_lines("c..\nc..");
+        let solution = Solver::new(&words, Comparison::Exact)
+            .solve(&crossword)
+            .unwrap();
+        assert_eq!(solution.words, [Some(0), Some(1)]);
+        assert_eq!(solution.ambiguous, [0, 1]);
+        assert!(
+            Solver::new(&words, Comparison::Exact)
+                .solve(&Crossword::from_lines("c..\nc..\nc.."))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_comparison() {
+        let words = dictionary(&["Été"]);
+        let crossword = Crossword::from_lines("e..");
+        assert!(
+            Solver::new(&words, Comparison::IgnoreCase)
+                .solve(&crossword)
+                .is_none()
+        );
+        let solution = Solver::new(&words, Comparison::IgnoreAccents)
+            .solve(&crossword)
+            .unwrap();
+        assert_eq!(solution.words, [Some(0)]);
+    }
+
+    #[test]
+    fn test_grid() {
+        let words = dictionary(&["tea", "tin", "nap", "tan"]);
+        let crossword = Crossword::from_grid("t..\n. #\n...");
+        let slots = crossword.slots();
+        assert_eq!(slots.len(), 3);
+        assert_eq!(
+            (slots[2].row, slots[2].col, slots[2].direction),
+            (0, 0, Direction::Down)
+        );
+        let solution = Solver::new(&words, Comparison::Exact)
+            .solve(&crossword)
+            .unwrap();
+        // only `nap` fits the bottom row, but the top row and the down word can trade places
+        assert_eq!(solution.words, [Some(0), Some(2), Some(1)]);
+        assert_eq!(solution.ambiguous, [0, 2]);
+    }
+
+    #[test]
+    fn test_given_slots_are_not_looked_up() {
+        let words = dictionary(&["ab"]);
+        let crossword = Crossword::from_grid("a.\nb ");
+        let solution = Solver::new(&words, Comparison::Exact)
+            .solve(&crossword)
+            .unwrap();
+        assert_eq!(solution.words, [Some(0), None]);
+    }
+}
@@ -1,8 +1,10 @@
-use std::fs;
+use crossword::solver::{Comparison, Crossword, Solver};
+use day06::mojibake::{self, NgramModel, Scorer, WordList};
+use std::path::Path;
+use std::{env, fs, io};
 
 fn unmash(word: &str) -> String {
-    let bytes = word.chars().map(|c| u8::try_from(c).unwrap()).collect();
-    String::from_utf8(bytes).unwrap()
+    mojibake::unmash(word).unwrap()
 }
 
 fn fix_words(words: &str) -> Vec<String> {
@@ -19,30 +21,70 @@ fn fix_words(words: &str) -> Vec<String> {
     fixed_words
 }
 
-fn find_match(blank: &str, words: &[String]) -> Option<usize> {
-    let length = blank.chars().count();
-    let index = blank.chars().position(|c| c != '.').unwrap();
-    let letter = blank.chars().nth(index);
-    for (i, word) in words.iter().enumerate() {
-        if word.chars().count() == length && word.chars().nth(index) == letter {
-            return Some(i + 1);
-        }
-    }
-    None
-}
-
 fn solution(input: &str) -> usize {
     let (words, crossword) = input.split_once("\n\n").unwrap();
     let words = fix_words(words);
-    let mut sum = 0;
-    for blank in crossword.lines().map(str::trim) {
-        let line_number = find_match(blank, &words).unwrap();
-        sum += line_number;
+    let crossword = Crossword::from_lines(crossword);
+    let solution = Solver::new(&words, Comparison::Exact)
+        .solve(&crossword)
+        .unwrap();
+    solution.words.iter().map(|word| word.unwrap() + 1).sum()
+}
+
+const REPAIR_USAGE: &str = "usage: day06 repair [--ngrams] FILE...";
+
+/// `repair [--ngrams] FILE...` repairs each line of stdin by whichever unmashing of it reads most
+/// like one of the languages given. each FILE, named after its language, is a list of words one
+/// per line, or with `--ngrams` running text to train a bigram model on
+fn repair(args: &[String]) {
+    let (ngrams, paths) = match args {
+        [flag, paths @ ..] if flag == "--ngrams" => (true, paths),
+        paths => (false, paths),
+    };
+    assert!(!paths.is_empty(), "{REPAIR_USAGE}");
+    let texts: Vec<String> = paths
+        .iter()
+        .map(|path| fs::read_to_string(path).unwrap())
+        .collect();
+    let scorers: Vec<Box<dyn Scorer>> = if ngrams {
+        let corpora: Vec<&str> = texts.iter().map(String::as_str).collect();
+        NgramModel::train_shared(&corpora)
+            .into_iter()
+            .map(|model| Box::new(model) as Box<dyn Scorer>)
+            .collect()
+    } else {
+        texts
+            .iter()
+            .map(|text| Box::new(WordList::new(text.lines())) as Box<dyn Scorer>)
+            .collect()
+    };
+    let scorers: Vec<(String, Box<dyn Scorer>)> = paths
+        .iter()
+        .map(|path| {
+            Path::new(path)
+                .file_stem()
+                .unwrap()
+                .to_string_lossy()
+                .into_owned()
+        })
+        .zip(scorers)
+        .collect();
+    let languages: Vec<(&str, &dyn Scorer)> = scorers
+        .iter()
+        .map(|(language, scorer)| (language.as_str(), scorer.as_ref()))
+        .collect();
+    for line in io::stdin().lines() {
+        let line = line.unwrap();
+        let (_, repair) = mojibake::repair_by_language(&line, &languages).unwrap();
+        println!("{}", repair.text);
     }
-    sum
 }
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("repair") {
+        return repair(&args[1..]);
+    }
     let input = fs::read_to_string("input").unwrap();
     let answer = solution(&input);
     println!("{answer}");
@@ -57,4 +99,15 @@ mod tests {
         let input = fs::read_to_string("test-input").unwrap();
         assert_eq!(solution(&input), 50);
     }
+
+    #[test]
+    fn test_scored_repair_agrees_with_line_numbers() {
+        let input = fs::read_to_string("test-input").unwrap();
+        let (words, _) = input.split_once("\n\n").unwrap();
+        let fixed = fix_words(words);
+        let word_list = WordList::new(fixed.iter().map(String::as_str));
+        for (word, fixed) in words.lines().zip(&fixed) {
+            assert_eq!(&mojibake::repair(word, &word_list).text, fixed);
+        }
+    }
 }
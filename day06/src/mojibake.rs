@@ -0,0 +1,244 @@
+use std::collections::{HashMap, HashSet};
+
+/// how many layers of UTF-8-read-as-Latin-1 mangling a repair will try to undo
+const MAX_DEPTH: usize = 3;
+
+/// reverses one round of UTF-8 bytes being misread as Latin-1, or `None` if `text` can't have
+/// been produced that way
+pub fn unmash(text: &str) -> Option<String> {
+    let bytes = text
+        .chars()
+        .map(|c| u8::try_from(c).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+/// `text` followed by each successive unmashing of it that is valid and changes something
+pub fn candidates(text: &str) -> Vec<String> {
+    let mut candidates = vec![text.to_owned()];
+    while candidates.len() <= MAX_DEPTH {
+        match unmash(candidates.last().unwrap()) {
+            Some(fixed) if &fixed != candidates.last().unwrap() => candidates.push(fixed),
+            _ => break,
+        }
+    }
+    candidates
+}
+
+/// rates how plausible `text` is as real text in some language, higher being more plausible
+pub trait Scorer {
+    fn score(&self, text: &str) -> f64;
+}
+
+/// scores text by the fraction of its words that appear in a word list
+pub struct WordList {
+    words: HashSet<String>,
+}
+
+impl WordList {
+    pub fn new<'a>(words: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            words: words.into_iter().map(str::to_lowercase).collect(),
+        }
+    }
+}
+
+impl Scorer for WordList {
+    fn score(&self, text: &str) -> f64 {
+        let words: Vec<String> = text
+            .split(|c: char| !c.is_alphabetic())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        if words.is_empty() {
+            return 0.0;
+        }
+        let known = words
+            .iter()
+            .filter(|&word| self.words.contains(word))
+            .count();
+        known as f64 / words.len() as f64
+    }
+}
+
+/// the bigram and unigram counts of a corpus
+type Counts = (HashMap<(char, char), u32>, HashMap<char, u32>);
+
+/// a character bigram model with add-one smoothing, scoring text by its mean log probability
+pub struct NgramModel {
+    bigrams: HashMap<(char, char), u32>,
+    unigrams: HashMap<char, u32>,
+    /// the number of characters smoothed over: those of the alphabet plus one for any unseen
+    vocabulary: usize,
+}
+
+impl NgramModel {
+    /// word boundaries are modelled as spaces, so `corpus` can be any running text
+    pub fn train(corpus: &str) -> Self {
+        let (bigrams, unigrams) = Self::count(corpus);
+        let vocabulary = unigrams.len() + 1;
+        Self {
+            bigrams,
+            unigrams,
+            vocabulary,
+        }
+    }
+
+    /// a model for each of `corpora`, all smoothed over the union of their alphabets. models
+    /// trained apart smooth over their own alphabets, which favours the language with fewer
+    /// letters, so only these have scores that can be compared across languages
+    pub fn train_shared(corpora: &[&str]) -> Vec<Self> {
+        let counts: Vec<_> = corpora.iter().map(|corpus| Self::count(corpus)).collect();
+        let alphabet: HashSet<char> = counts
+            .iter()
+            .flat_map(|(_, unigrams)| unigrams.keys().copied())
+            .collect();
+        counts
+            .into_iter()
+            .map(|(bigrams, unigrams)| Self {
+                bigrams,
+                unigrams,
+                vocabulary: alphabet.len() + 1,
+            })
+            .collect()
+    }
+
+    fn count(corpus: &str) -> Counts {
+        let mut bigrams = HashMap::new();
+        let mut unigrams = HashMap::new();
+        for word in corpus.split_whitespace() {
+            let chars: Vec<char> = format!(" {} ", word.to_lowercase()).chars().collect();
+            for pair in chars.windows(2) {
+                *bigrams.entry((pair[0], pair[1])).or_insert(0) += 1;
+                *unigrams.entry(pair[0]).or_insert(0) += 1;
+            }
+        }
+        (bigrams, unigrams)
+    }
+}
+
+impl Scorer for NgramModel {
+    fn score(&self, text: &str) -> f64 {
+        let vocabulary = self.vocabulary as f64;
+        let mut total = 0.0;
+        let mut count = 0;
+        for word in text.split_whitespace() {
+            let chars: Vec<char> = format!(" {} ", word.to_lowercase()).chars().collect();
+            for pair in chars.windows(2) {
+                let bigram = self.bigrams.get(&(pair[0], pair[1])).copied().unwrap_or(0);
+                let unigram = self.unigrams.get(&pair[0]).copied().unwrap_or(0);
+                total += ((f64::from(bigram) + 1.0) / (f64::from(unigram) + vocabulary)).ln();
+                count += 1;
+            }
+        }
+        if count == 0 {
+            0.0
+        } else {
+            total / f64::from(count)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Repair {
+    pub text: String,
+    /// the number of unmashing rounds applied
+    pub depth: usize,
+    pub score: f64,
+}
+
+/// picks the candidate repair of `text` that `scorer` finds most plausible, preferring fewer
+/// rounds of unmashing on a tie
+pub fn repair(text: &str, scorer: &dyn Scorer) -> Repair {
+    let mut best: Option<Repair> = None;
+    for (depth, candidate) in candidates(text).into_iter().enumerate() {
+        let score = scorer.score(&candidate);
+        if best.as_ref().is_none_or(|best| score > best.score) {
+            best = Some(Repair {
+                text: candidate,
+                depth,
+                score,
+            });
+        }
+    }
+    best.unwrap()
+}
+
+/// repairs `text` against every language's model, returning the language whose best repair
+/// scored highest along with that repair. the scores are compared as they are, so bigram models
+/// should come from [`NgramModel::train_shared`]
+pub fn repair_by_language<'a>(
+    text: &str,
+    languages: &[(&'a str, &dyn Scorer)],
+) -> Option<(&'a str, Repair)> {
+    languages
+        .iter()
+        .map(|&(language, scorer)| (language, repair(text, scorer)))
+        .reduce(|best, next| {
+            if next.1.score > best.1.score {
+                next
+            } else {
+                best
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUTCH: &str = "de religiën van het land zijn één geheel; de roekoeën van duiven en \
+        de geleën kerken op de heuvel";
+    const FRENCH: &str = "les dardées du soleil sont tancées par le vent, et les molières \
+        amènent un été frais à la crème";
+
+    #[test]
+    fn test_unmash() {
+        assert_eq!(unmash("religiÃ«n").as_deref(), Some("religiën"));
+        assert_eq!(unmash("träffs"), None);
+        assert_eq!(unmash("日本"), None);
+    }
+
+    #[test]
+    fn test_candidates() {
+        assert_eq!(
+            candidates("pugilar\u{c3}\u{83}\u{c2}\u{a3}o"),
+            [
+                "pugilar\u{c3}\u{83}\u{c2}\u{a3}o",
+                "pugilar\u{c3}\u{a3}o",
+                "pugilarão"
+            ]
+        );
+        assert_eq!(candidates("ondulât"), ["ondulât"]);
+        assert_eq!(candidates("geleet"), ["geleet"]);
+    }
+
+    #[test]
+    fn test_repair_word_list() {
+        let words = WordList::new(["religiën", "dardées"]);
+        assert_eq!(repair("religiÃ«n", &words).text, "religiën");
+        assert_eq!(repair("dardÃ©es", &words).depth, 1);
+        assert_eq!(repair("unknown", &words).depth, 0);
+    }
+
+    #[test]
+    fn test_repair_ngrams() {
+        let mut models = NgramModel::train_shared(&[DUTCH, FRENCH]);
+        let (french, dutch) = (models.pop().unwrap(), models.pop().unwrap());
+        assert_eq!(repair("roekoeÃ«n", &dutch).text, "roekoeën");
+        assert_eq!(repair("geleën", &dutch).text, "geleën");
+        let (language, repair) =
+            repair_by_language("tancÃ©es", &[("nl", &dutch), ("fr", &french)]).unwrap();
+        assert_eq!(language, "fr");
+        assert_eq!(repair.text, "tancées");
+    }
+
+    #[test]
+    fn test_shared_smoothing() {
+        // two words each, so text neither has seen is only told apart by the smoothing
+        let (small, large) = ("ab ba", "cdefg hijkl");
+        assert!(NgramModel::train(small).score("zz") > NgramModel::train(large).score("zz"));
+        let shared = NgramModel::train_shared(&[small, large]);
+        assert_eq!(shared[0].score("zz"), shared[1].score("zz"));
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt;
+use unicode_normalization::{
+    IsNormalized, UnicodeNormalization, is_nfc_quick, is_nfd_quick, is_nfkc_quick, is_nfkd_quick,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Form {
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+}
+
+impl Form {
+    pub const ALL: [Self; 4] = [Self::Nfc, Self::Nfd, Self::Nfkc, Self::Nfkd];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Nfc => "NFC",
+            Self::Nfd => "NFD",
+            Self::Nfkc => "NFKC",
+            Self::Nfkd => "NFKD",
+        }
+    }
+
+    pub fn normalize(self, s: &str) -> String {
+        match self {
+            Self::Nfc => s.nfc().collect(),
+            Self::Nfd => s.nfd().collect(),
+            Self::Nfkc => s.nfkc().collect(),
+            Self::Nfkd => s.nfkd().collect(),
+        }
+    }
+
+    /// the Quick_Check property for `s`, which may be `Maybe` where only a full normalisation
+    /// can tell
+    pub fn quick_check(self, s: &str) -> IsNormalized {
+        match self {
+            Self::Nfc => is_nfc_quick(s.chars()),
+            Self::Nfd => is_nfd_quick(s.chars()),
+            Self::Nfkc => is_nfkc_quick(s.chars()),
+            Self::Nfkd => is_nfkd_quick(s.chars()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct FormReport {
+    pub form: Form,
+    pub text: String,
+    /// whether normalising changed the input
+    pub differs: bool,
+    pub quick_check: IsNormalized,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Report {
+    pub input: String,
+    pub forms: Vec<FormReport>,
+    /// characters whose compatibility decomposition differs from their canonical one, with the
+    /// compatibility decomposition
+    pub compatibility: Vec<(char, String)>,
+}
+
+/// formats each character of `s` as `U+XXXX`, separated by spaces
+pub fn code_points(s: &str) -> String {
+    s.chars()
+        .map(|c| format!("U+{:04X}", u32::from(c)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn explore(input: &str) -> Report {
+    let forms = Form::ALL
+        .iter()
+        .map(|&form| {
+            let text = form.normalize(input);
+            FormReport {
+                form,
+                differs: text != input,
+                quick_check: form.quick_check(input),
+                text,
+            }
+        })
+        .collect();
+    let mut compatibility: Vec<(char, String)> = Vec::new();
+    for c in input.chars() {
+        let nfkd: String = c.nfkd().collect();
+        if nfkd != c.nfd().collect::<String>() && !compatibility.iter().any(|&(d, _)| d == c) {
+            compatibility.push((c, nfkd));
+        }
+    }
+    Report {
+        input: input.to_owned(),
+        forms,
+        compatibility,
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:?} {}", self.input, code_points(&self.input))?;
+        for form in &self.forms {
+            writeln!(
+                f,
+                "  {:<4} {:?} {} ({}, quick check: {:?})",
+                form.form.name(),
+                form.text,
+                code_points(&form.text),
+                if form.differs { "differs" } else { "same" },
+                form.quick_check,
+            )?;
+        }
+        for (c, decomposition) in &self.compatibility {
+            writeln!(
+                f,
+                "  compatibility: {c:?} {} -> {}",
+                code_points(&c.to_string()),
+                code_points(decomposition)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_points() {
+        assert_eq!(code_points("e\u{301}"), "U+0065 U+0301");
+        assert_eq!(code_points("😀"), "U+1F600");
+    }
+
+    #[test]
+    fn test_explore() {
+        let report = explore("e\u{301}ﬁ");
+        let forms: Vec<(&str, bool, IsNormalized)> = report
+            .forms
+            .into_iter()
+            .map(|form| (form.form.name(), form.differs, form.quick_check))
+            .collect();
+        assert_eq!(
+            forms,
+            [
+                ("NFC", true, IsNormalized::Maybe),
+                ("NFD", false, IsNormalized::Yes),
+                ("NFKC", true, IsNormalized::No),
+                ("NFKD", true, IsNormalized::No),
+            ]
+        );
+        assert_eq!(report.compatibility, [('ﬁ', "fi".to_owned())]);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            explore("Å").to_string(),
+            "\"Å\" U+00C5\n  \
+             NFC  \"Å\" U+00C5 (same, quick check: Yes)\n  \
+             NFD  \"A\\u{30a}\" U+0041 U+030A (differs, quick check: No)\n  \
+             NFKC \"Å\" U+00C5 (same, quick check: Yes)\n  \
+             NFKD \"A\\u{30a}\" U+0041 U+030A (differs, quick check: No)\n"
+        );
+    }
+}
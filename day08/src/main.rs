@@ -1,5 +1,5 @@
 use std::collections::HashSet;
-use std::fs;
+use std::{env, fs, io};
 use unicode_normalization::UnicodeNormalization;
 
 const VOWELS: &str = "aeiou";
@@ -30,7 +30,26 @@ fn solution(input: &str) -> usize {
     input.lines().filter(|pwd| is_valid(pwd)).count()
 }
 
+/// `explore [STRING]...` prints every normalisation form of each string, or of each line of
+/// stdin if none are given
+fn explore(strings: &[String]) {
+    if strings.is_empty() {
+        for line in io::stdin().lines() {
+            print!("{}", day08::explore::explore(&line.unwrap()));
+        }
+    } else {
+        for string in strings {
+            print!("{}", day08::explore::explore(string));
+        }
+    }
+}
+
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "explore") {
+        explore(&args[1..]);
+        return;
+    }
     let input = fs::read_to_string("input").unwrap();
     let answer = solution(&input);
     println!("{answer}");
@@ -0,0 +1,109 @@
+/// the calendar a date is written in. both are proleptic, i.e. their rules are applied to every
+/// year rather than switching over at some historical date
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Calendar {
+    #[default]
+    Gregorian,
+    Julian,
+}
+
+impl Calendar {
+    pub fn is_leap_year(self, year: i32) -> bool {
+        match self {
+            Self::Gregorian => year % 4 == 0 && (year % 100 != 0 || year % 400 == 0),
+            Self::Julian => year.rem_euclid(4) == 0,
+        }
+    }
+
+    /// the number of days in `month` (1 to 12) of `year`, or `None` for an invalid month
+    pub fn days_in_month(self, year: i32, month: u8) -> Option<u8> {
+        match month {
+            2 if self.is_leap_year(year) => Some(29),
+            2 => Some(28),
+            4 | 6 | 9 | 11 => Some(30),
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => Some(31),
+            _ => None,
+        }
+    }
+
+    pub fn is_valid(self, year: i32, month: u8, day: u8) -> bool {
+        self.days_in_month(year, month)
+            .is_some_and(|days| (1..=days).contains(&day))
+    }
+}
+
+/// the hundred-year span that two-digit years are read into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct YearWindow {
+    /// the earliest year in the window
+    pub start: i32,
+}
+
+impl YearWindow {
+    pub fn new(start: i32) -> Self {
+        Self { start }
+    }
+
+    /// a window ending `years_ahead` years after `year`, e.g. for reading dates relative to
+    /// today
+    pub fn sliding(year: i32, years_ahead: i32) -> Self {
+        Self::new(year + years_ahead - 99)
+    }
+
+    /// the year in the window ending in the two digits `yy`, or `None` if `yy` is over 99
+    pub fn expand(self, yy: u16) -> Option<i32> {
+        if yy > 99 {
+            return None;
+        }
+        let offset = (i32::from(yy) - self.start).rem_euclid(100);
+        Some(self.start + offset)
+    }
+}
+
+impl Default for YearWindow {
+    fn default() -> Self {
+        Self::new(1920)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leap_years() {
+        let gregorian = Calendar::Gregorian;
+        assert!(gregorian.is_leap_year(2000));
+        assert!(gregorian.is_leap_year(2024));
+        assert!(!gregorian.is_leap_year(1900));
+        assert!(!gregorian.is_leap_year(2100));
+        assert!(!gregorian.is_leap_year(2023));
+        let julian = Calendar::Julian;
+        assert!(julian.is_leap_year(1900));
+        assert!(julian.is_leap_year(-4));
+        assert!(!julian.is_leap_year(1901));
+    }
+
+    #[test]
+    fn test_is_valid() {
+        assert!(Calendar::Gregorian.is_valid(2000, 2, 29));
+        assert!(!Calendar::Gregorian.is_valid(1900, 2, 29));
+        assert!(Calendar::Julian.is_valid(1900, 2, 29));
+        assert!(!Calendar::Gregorian.is_valid(2001, 4, 31));
+        assert!(!Calendar::Gregorian.is_valid(2001, 13, 1));
+        assert!(!Calendar::Gregorian.is_valid(2001, 1, 0));
+    }
+
+    #[test]
+    fn test_year_window() {
+        let window = YearWindow::default();
+        assert_eq!(window.expand(20), Some(1920));
+        assert_eq!(window.expand(99), Some(1999));
+        assert_eq!(window.expand(0), Some(2000));
+        assert_eq!(window.expand(19), Some(2019));
+        assert_eq!(window.expand(100), None);
+        let window = YearWindow::sliding(2026, 10);
+        assert_eq!(window.expand(36), Some(2036));
+        assert_eq!(window.expand(37), Some(1937));
+    }
+}
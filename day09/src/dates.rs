@@ -0,0 +1,226 @@
+use crate::calendar::{Calendar, YearWindow};
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl fmt::Display for Date {
+    /// formats as ISO 8601, e.g. `2001-09-11`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Part {
+    value: u16,
+    digits: usize,
+}
+
+/// a date as written, with three numeric fields in an unknown order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawDate([Part; 3]);
+
+impl FromStr for RawDate {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().split(['-', '/', '.']).map(|part| {
+            if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+                return Err("date fields must be numbers");
+            }
+            let value = part.parse().map_err(|_| "date field is too large")?;
+            Ok(Part {
+                value,
+                digits: part.len(),
+            })
+        });
+        let (Some(a), Some(b), Some(c), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err("date must have three fields");
+        };
+        Ok(Self([a?, b?, c?]))
+    }
+}
+
+/// the order in which day, month and year are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DateOrder {
+    Dmy,
+    Dym,
+    Mdy,
+    Myd,
+    Ydm,
+    Ymd,
+}
+
+impl DateOrder {
+    pub const ALL: [Self; 6] = [
+        Self::Dmy,
+        Self::Dym,
+        Self::Mdy,
+        Self::Myd,
+        Self::Ydm,
+        Self::Ymd,
+    ];
+
+    /// the positions of the day, month and year fields
+    fn positions(self) -> (usize, usize, usize) {
+        match self {
+            Self::Dmy => (0, 1, 2),
+            Self::Dym => (0, 2, 1),
+            Self::Mdy => (1, 0, 2),
+            Self::Myd => (2, 0, 1),
+            Self::Ydm => (1, 2, 0),
+            Self::Ymd => (2, 1, 0),
+        }
+    }
+}
+
+impl fmt::Display for DateOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Dmy => "DMY",
+            Self::Dym => "DYM",
+            Self::Mdy => "MDY",
+            Self::Myd => "MYD",
+            Self::Ydm => "YDM",
+            Self::Ymd => "YMD",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Resolver {
+    /// where two-digit years are placed
+    pub window: YearWindow,
+    pub calendar: Calendar,
+}
+
+impl Resolver {
+    /// reads `raw` in the given order, or `None` if that doesn't give a real date
+    pub fn interpret(&self, raw: &RawDate, order: DateOrder) -> Option<Date> {
+        let (d, m, y) = order.positions();
+        let (day, month, year) = (raw.0[d], raw.0[m], raw.0[y]);
+        if day.digits > 2 || month.digits > 2 {
+            return None;
+        }
+        let year = match year.digits {
+            1 | 2 => self.window.expand(year.value)?,
+            4 => i32::from(year.value),
+            _ => return None,
+        };
+        let month = u8::try_from(month.value).ok()?;
+        let day = u8::try_from(day.value).ok()?;
+        self.calendar
+            .is_valid(year, month, day)
+            .then_some(Date { year, month, day })
+    }
+
+    /// every order under which all of `dates` are real dates
+    pub fn resolve(&self, dates: &[RawDate]) -> Vec<DateOrder> {
+        DateOrder::ALL
+            .into_iter()
+            .filter(|&order| dates.iter().all(|raw| self.interpret(raw, order).is_some()))
+            .collect()
+    }
+
+    /// reads all of `dates` in the given order, or `None` if any of them isn't a real date
+    pub fn normalise(&self, dates: &[RawDate], order: DateOrder) -> Option<Vec<Date>> {
+        dates.iter().map(|raw| self.interpret(raw, order)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw_dates(dates: &[&str]) -> Vec<RawDate> {
+        dates.iter().map(|date| date.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!("16-05-18".parse::<RawDate>().is_ok());
+        assert!("2001/9/11".parse::<RawDate>().is_ok());
+        assert!("16-05".parse::<RawDate>().is_err());
+        assert!("16-05-18-01".parse::<RawDate>().is_err());
+        assert!("16-May-18".parse::<RawDate>().is_err());
+    }
+
+    #[test]
+    fn test_interpret() {
+        let resolver = Resolver::default();
+        let raw = "09-11-01".parse().unwrap();
+        assert_eq!(
+            resolver.interpret(&raw, DateOrder::Mdy),
+            Some(Date {
+                year: 2001,
+                month: 9,
+                day: 11
+            })
+        );
+        assert_eq!(
+            resolver
+                .interpret(&raw, DateOrder::Ydm)
+                .unwrap()
+                .to_string(),
+            "2009-01-11"
+        );
+        let raw = "29-02-00".parse().unwrap();
+        assert!(resolver.interpret(&raw, DateOrder::Dmy).is_some());
+        assert!(
+            Resolver {
+                window: YearWindow::new(1900),
+                calendar: Calendar::Gregorian,
+            }
+            .interpret(&raw, DateOrder::Dmy)
+            .is_none()
+        );
+        assert!(
+            Resolver {
+                window: YearWindow::new(1900),
+                calendar: Calendar::Julian,
+            }
+            .interpret(&raw, DateOrder::Dmy)
+            .is_some()
+        );
+        let raw = "2001-09-11".parse().unwrap();
+        assert!(resolver.interpret(&raw, DateOrder::Ymd).is_some());
+        assert!(resolver.interpret(&raw, DateOrder::Dmy).is_none());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let resolver = Resolver::default();
+        let dates = raw_dates(&["02-17-04", "06-02-29", "09-11-01"]);
+        assert_eq!(resolver.resolve(&dates), [DateOrder::Mdy, DateOrder::Myd]);
+        let dates = raw_dates(&["16-05-18", "06-02-29", "11-09-01"]);
+        assert_eq!(resolver.resolve(&dates), [DateOrder::Dmy]);
+        let dates = raw_dates(&["01-02-03"]);
+        assert_eq!(resolver.resolve(&dates), DateOrder::ALL);
+        let dates = raw_dates(&["31-09-11", "02-17-04"]);
+        assert_eq!(resolver.resolve(&dates), [DateOrder::Ydm]);
+        let dates = raw_dates(&["31-09-11", "02-17-04", "13-13-13"]);
+        assert_eq!(resolver.resolve(&dates), []);
+    }
+
+    #[test]
+    fn test_normalise() {
+        let resolver = Resolver::default();
+        let dates = raw_dates(&["02-17-04", "09-11-01"]);
+        let iso: Vec<String> = resolver
+            .normalise(&dates, DateOrder::Mdy)
+            .unwrap()
+            .iter()
+            .map(Date::to_string)
+            .collect();
+        assert_eq!(iso, ["2004-02-17", "2001-09-11"]);
+        assert_eq!(resolver.normalise(&dates, DateOrder::Dmy), None);
+    }
+}
@@ -1,58 +1,38 @@
-use std::collections::HashMap;
+use day09::dates::{Date, DateOrder, Resolver};
+use day09::records::RecordStore;
 use std::fs;
 
-type Date = (u8, u8, u8);
-
-fn parse_date(date: &str) -> Date {
-    let nums: Vec<u8> = date.split('-').map(|n| n.parse().unwrap()).collect();
-    (nums[0], nums[1], nums[2])
-}
-
-fn parse(input: &str) -> HashMap<&str, Vec<Date>> {
-    let mut name_to_dates = HashMap::new();
-    for line in input.lines() {
-        let (date, names) = line.split_once(':').unwrap();
-        let date = parse_date(date);
-        for name in names.split(',').map(str::trim) {
-            name_to_dates.entry(name).or_insert(Vec::new()).push(date);
-        }
-    }
-    name_to_dates
-}
-
-fn is_valid_dmy((d, m, y): Date) -> bool {
-    d != 0
-        && (m == 2 && d <= 29
-            || [1, 3, 5, 7, 8, 10, 12].contains(&m) && d <= 31
-            || [4, 6, 9, 11].contains(&m) && d <= 30)
-        && !(d == 29 && m == 2 && y % 4 != 0)
-}
-
-fn is_valid_mdy((m, d, y): Date) -> bool {
-    is_valid_dmy((d, m, y))
-}
-
-fn is_valid_ymd((y, m, d): Date) -> bool {
-    is_valid_dmy((d, m, y))
-}
-
-fn is_valid_ydm((y, d, m): Date) -> bool {
-    is_valid_dmy((d, m, y))
-}
+const TARGET: Date = Date {
+    year: 2001,
+    month: 9,
+    day: 11,
+};
+
+/// the orders the puzzle's forms were filled in with; the resolver also knows DYM and MYD, but
+/// reading dates that way could match authors the puzzle doesn't mean
+const ORDERS: [DateOrder; 4] = [
+    DateOrder::Dmy,
+    DateOrder::Mdy,
+    DateOrder::Ymd,
+    DateOrder::Ydm,
+];
 
 fn solution(input: &str) -> String {
-    let name_to_dates = parse(input);
+    let mut store = RecordStore::from_lines(input).unwrap();
+    let resolver = Resolver::default();
+    store.resolve(&resolver);
     let mut names = Vec::new();
-    for (&name, dates) in &name_to_dates {
-        if dates.iter().all(|&date| is_valid_dmy(date)) && dates.contains(&(11, 9, 1))
-            || dates.iter().all(|&date| is_valid_mdy(date)) && dates.contains(&(9, 11, 1))
-            || dates.iter().all(|&date| is_valid_ymd(date)) && dates.contains(&(1, 9, 11))
-            || dates.iter().all(|&date| is_valid_ydm(date)) && dates.contains(&(1, 11, 9))
+    for author in store.authors() {
+        let dates = author.raw_dates();
+        if author
+            .orders
+            .iter()
+            .filter(|order| ORDERS.contains(order))
+            .any(|&order| resolver.normalise(&dates, order).unwrap().contains(&TARGET))
         {
-            names.push(name);
+            names.push(author.name.as_str());
         }
     }
-    names.sort_unstable();
     names.join(" ")
 }
 
@@ -71,4 +51,11 @@ mod tests {
         let input = fs::read_to_string("test-input").unwrap();
         assert_eq!(solution(&input), "Margot Peter");
     }
+
+    #[test]
+    fn test_only_puzzle_orders() {
+        // only DYM reads both of these as real dates, and it reads the first as 2001-09-11
+        assert_eq!(solution("11-01-09: Ann\n31-99-12: Ann\n"), "");
+        assert_eq!(solution("11-09-01: Ann\n31-12-99: Ann\n"), "Ann");
+    }
 }
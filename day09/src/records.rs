@@ -0,0 +1,348 @@
+use crate::dates::{Date, DateOrder, RawDate, Resolver};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// the date exactly as it was written
+    pub raw: String,
+    parsed: Option<RawDate>,
+    /// the date once the author's convention is known, if every consistent convention agrees
+    pub date: Option<Date>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Author {
+    pub name: String,
+    pub entries: Vec<Entry>,
+    /// every convention under which all of this author's entries are real dates
+    pub orders: Vec<DateOrder>,
+}
+
+impl Author {
+    /// the parsed form of every entry, skipping any that couldn't be parsed
+    pub fn raw_dates(&self) -> Vec<RawDate> {
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.parsed)
+            .collect()
+    }
+
+    /// no convention makes sense of every entry
+    pub fn is_contradictory(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    /// the author's convention, if exactly one fits
+    pub fn order(&self) -> Option<DateOrder> {
+        match self.orders[..] {
+            [order] => Some(order),
+            _ => None,
+        }
+    }
+
+    pub fn convention(&self) -> String {
+        if self.is_contradictory() {
+            return "contradictory".to_owned();
+        }
+        let orders: Vec<String> = self.orders.iter().map(DateOrder::to_string).collect();
+        orders.join("/")
+    }
+
+    fn resolve(&mut self, resolver: &Resolver) {
+        let raw_dates = self.raw_dates();
+        self.orders = if raw_dates.len() == self.entries.len() {
+            resolver.resolve(&raw_dates)
+        } else {
+            Vec::new()
+        };
+        for entry in &mut self.entries {
+            let mut dates = self
+                .orders
+                .iter()
+                .map(|&order| resolver.interpret(&entry.parsed.unwrap(), order));
+            let first = dates.next().flatten();
+            entry.date = first.filter(|_| dates.all(|date| date == first));
+        }
+    }
+}
+
+/// an entry as it appears in an imported file. an export has the original string in `raw` and
+/// the resolved date in `date`, so `raw` takes precedence when re-importing one
+#[derive(Debug, Deserialize)]
+struct ImportedEntry {
+    author: String,
+    date: Option<String>,
+    raw: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ExportedEntry<'a> {
+    author: &'a str,
+    raw: &'a str,
+    date: Option<String>,
+    convention: String,
+}
+
+/// the authors of a set of legacy forms and every date each of them wrote
+#[derive(Debug, Default)]
+pub struct RecordStore {
+    authors: BTreeMap<String, Author>,
+}
+
+/// splits one CSV record, honouring double-quoted fields. quoted fields may not span lines
+fn parse_csv_line(line: &str) -> Result<Vec<String>, &'static str> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().unwrap();
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(String::new()),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("unterminated quoted field");
+    }
+    Ok(fields)
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+impl RecordStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, author: &str, raw: &str) {
+        let raw = raw.trim();
+        self.authors
+            .entry(author.to_owned())
+            .or_insert_with(|| Author {
+                name: author.to_owned(),
+                entries: Vec::new(),
+                orders: Vec::new(),
+            })
+            .entries
+            .push(Entry {
+                raw: raw.to_owned(),
+                parsed: raw.parse().ok(),
+                date: None,
+            });
+    }
+
+    /// reads `date: name, name` lines
+    pub fn from_lines(input: &str) -> Result<Self, &'static str> {
+        let mut store = Self::new();
+        for line in input.lines().filter(|line| !line.trim().is_empty()) {
+            let (date, names) = line.split_once(':').ok_or("missing ':' after date")?;
+            for name in names.split(',').map(str::trim) {
+                store.add(name, date);
+            }
+        }
+        Ok(store)
+    }
+
+    /// reads CSV with a header row naming an `author` column and a `raw` or `date` column. as
+    /// in JSON, `raw` takes precedence when there are both, wherever it is in the header
+    pub fn from_csv(input: &str) -> Result<Self, &'static str> {
+        let mut lines = input.lines().filter(|line| !line.trim().is_empty());
+        let header = parse_csv_line(lines.next().ok_or("missing header")?)?;
+        let column = |name: &str| {
+            header
+                .iter()
+                .position(|field| field.trim().to_lowercase() == name)
+        };
+        let author = column("author").ok_or("missing author column")?;
+        let date = column("raw")
+            .or_else(|| column("date"))
+            .ok_or("missing date column")?;
+        let mut store = Self::new();
+        for line in lines {
+            let fields = parse_csv_line(line)?;
+            let (Some(author), Some(date)) = (fields.get(author), fields.get(date)) else {
+                return Err("record has too few fields");
+            };
+            store.add(author.trim(), date);
+        }
+        Ok(store)
+    }
+
+    /// reads a JSON array of `{"author": ..., "date": ...}` objects, or a previous export
+    pub fn from_json(input: &str) -> Result<Self, &'static str> {
+        let entries: Vec<ImportedEntry> =
+            serde_json::from_str(input).map_err(|_| "invalid JSON")?;
+        let mut store = Self::new();
+        for entry in entries {
+            let date = entry.raw.or(entry.date).ok_or("entry has no date")?;
+            store.add(&entry.author, &date);
+        }
+        Ok(store)
+    }
+
+    /// infers every author's convention and resolves their dates with it
+    pub fn resolve(&mut self, resolver: &Resolver) {
+        for author in self.authors.values_mut() {
+            author.resolve(resolver);
+        }
+    }
+
+    /// every author, in name order
+    pub fn authors(&self) -> impl Iterator<Item = &Author> {
+        self.authors.values()
+    }
+
+    pub fn author(&self, name: &str) -> Option<&Author> {
+        self.authors.get(name)
+    }
+
+    /// names of authors whose entries fit no convention
+    pub fn contradictory(&self) -> Vec<&str> {
+        self.authors()
+            .filter(|author| author.is_contradictory())
+            .map(|author| author.name.as_str())
+            .collect()
+    }
+
+    fn exported(&self) -> Vec<ExportedEntry<'_>> {
+        self.authors()
+            .flat_map(|author| {
+                let convention = author.convention();
+                author.entries.iter().map(move |entry| ExportedEntry {
+                    author: &author.name,
+                    raw: &entry.raw,
+                    date: entry.date.map(|date| date.to_string()),
+                    convention: convention.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// one row per entry with columns `author,raw,date,convention`, dates in ISO 8601
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("author,raw,date,convention\n");
+        for entry in self.exported() {
+            let fields = [
+                entry.author,
+                entry.raw,
+                entry.date.as_deref().unwrap_or(""),
+                &entry.convention,
+            ];
+            let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.exported()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINES: &str = "16-05-18: Margot, Frank\n\
+                         02-17-04: Peter, Elise\n\
+                         06-02-29: Peter, Margot\n\
+                         31-09-11: Elise, Frank\n\
+                         09-11-01: Peter, Frank, Elise\n\
+                         11-09-01: Margot, Frank";
+
+    fn resolved(mut store: RecordStore) -> RecordStore {
+        store.resolve(&Resolver::default());
+        store
+    }
+
+    #[test]
+    fn test_parse_csv_line() {
+        assert_eq!(parse_csv_line("a,b").unwrap(), ["a", "b"]);
+        assert_eq!(
+            parse_csv_line(r#""Smith, John","say ""hi""",x"#).unwrap(),
+            ["Smith, John", r#"say "hi""#, "x"]
+        );
+        assert!(parse_csv_line(r#""open"#).is_err());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut store = RecordStore::from_lines(LINES).unwrap();
+        store.add("Frank", "13-13-13");
+        let store = resolved(store);
+        let margot = store.author("Margot").unwrap();
+        assert_eq!(margot.order(), Some(DateOrder::Dmy));
+        assert_eq!(margot.entries[2].date.unwrap().to_string(), "2001-09-11");
+        let peter = store.author("Peter").unwrap();
+        assert_eq!(peter.convention(), "MDY/MYD");
+        assert!(peter.entries.iter().all(|entry| entry.date.is_none()));
+        assert_eq!(store.author("Elise").unwrap().order(), Some(DateOrder::Ydm));
+        assert_eq!(store.contradictory(), ["Frank"]);
+    }
+
+    #[test]
+    fn test_unparseable_entry_is_contradictory() {
+        let mut store = RecordStore::new();
+        store.add("Ann", "01-02-03");
+        store.add("Ann", "unknown");
+        let store = resolved(store);
+        assert_eq!(store.contradictory(), ["Ann"]);
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let csv = "id,Author,Date\n1,\"Smith, J\",2001-09-11\n2,\"Smith, J\",1999-12-31\n";
+        let store = resolved(RecordStore::from_csv(csv).unwrap());
+        let exported = store.to_csv();
+        assert_eq!(
+            exported,
+            "author,raw,date,convention\n\
+             \"Smith, J\",2001-09-11,2001-09-11,YMD\n\
+             \"Smith, J\",1999-12-31,1999-12-31,YMD\n"
+        );
+        let store = resolved(RecordStore::from_csv(&exported).unwrap());
+        assert_eq!(store.to_csv(), exported);
+    }
+
+    #[test]
+    fn test_csv_raw_takes_precedence() {
+        for csv in [
+            "author,date,raw\nAnn,2001-09-11,11-09-01\n",
+            "author,raw,date\nAnn,11-09-01,2001-09-11\n",
+        ] {
+            let store = RecordStore::from_csv(csv).unwrap();
+            assert_eq!(
+                store.author("Ann").unwrap().entries[0].raw,
+                "11-09-01",
+                "{csv}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let json =
+            r#"[{"author": "Elise", "date": "02-17-04"}, {"author": "Elise", "date": "13-13-13"}]"#;
+        let store = resolved(RecordStore::from_json(json).unwrap());
+        assert!(store.author("Elise").unwrap().is_contradictory());
+        let exported = store.to_json();
+        assert!(exported.contains(r#""convention": "contradictory""#));
+        let store = RecordStore::from_json(&exported).unwrap();
+        assert_eq!(store.author("Elise").unwrap().entries.len(), 2);
+        assert!(RecordStore::from_json("{").is_err());
+    }
+}
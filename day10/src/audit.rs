@@ -0,0 +1,240 @@
+use crate::cache::{Cache, CacheError};
+use crate::hash;
+use crate::verify::{Form, Verifier};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use unicode_normalization::UnicodeNormalization;
+
+/// how a login attempt fared against the stored hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// the attempt matched exactly as typed
+    Success,
+    /// the attempt only matched once normalised, so a byte-for-byte check rejected it
+    NormalisationMismatch,
+    Failure,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Lockout {
+    /// consecutive failures after which the account is locked. 0 disables the lockout
+    pub threshold: usize,
+    /// whether normalisation mismatches count as successes rather than failures
+    pub normalised: bool,
+}
+
+impl Default for Lockout {
+    fn default() -> Self {
+        Self {
+            threshold: 3,
+            normalised: false,
+        }
+    }
+}
+
+/// one user's attempts, identified by their 1-based position in the whole attempt log
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct UserReport {
+    pub user: String,
+    pub attempts: usize,
+    pub succeeded: Vec<usize>,
+    pub failed: Vec<usize>,
+    /// failed attempts that would have passed under NFC
+    pub normalisation_mismatches: Vec<usize>,
+    /// the attempt that triggered the simulated lockout
+    pub locked_out_at: Option<usize>,
+    /// attempts made while locked out, which are neither verified nor counted as succeeded or
+    /// failed
+    pub blocked: Vec<usize>,
+}
+
+fn outcome(form: Option<Form>, hash: &str, attempt: &str) -> Result<Outcome, hash::HashError> {
+    let exact = match form {
+        None => return Ok(Outcome::Failure),
+        Some(Form::Nfc) => attempt.nfc().eq(attempt.chars()),
+        Some(Form::Nfd) => attempt.nfd().eq(attempt.chars()),
+        Some(Form::Nfkc) => attempt.nfkc().eq(attempt.chars()),
+        // the matching mixture may not be the one that was typed
+        Some(Form::Mixed) => hash::verify(attempt, hash)?,
+    };
+    Ok(if exact {
+        Outcome::Success
+    } else {
+        Outcome::NormalisationMismatch
+    })
+}
+
+/// classifies every attempt in `attempts` against `hash_db` and simulates `lockout`, returning
+/// one report per user in name order. users without a stored hash fail every attempt
+pub fn audit(
+    hash_db: &HashMap<&str, &str>,
+    attempts: &[(&str, &str)],
+    verifier: &Verifier,
+    cache: &mut Cache,
+    lockout: Lockout,
+) -> Result<Vec<UserReport>, CacheError> {
+    let mut reports: BTreeMap<&str, UserReport> = BTreeMap::new();
+    let mut consecutive_failures: HashMap<&str, usize> = HashMap::new();
+    for (i, &(user, attempt)) in attempts.iter().enumerate() {
+        let number = i + 1;
+        let report = reports.entry(user).or_insert_with(|| UserReport {
+            user: user.to_owned(),
+            ..UserReport::default()
+        });
+        report.attempts += 1;
+        if report.locked_out_at.is_some() {
+            report.blocked.push(number);
+            continue;
+        }
+        let outcome = match hash_db.get(user) {
+            Some(hash) => outcome(cache.verify(verifier, user, hash, attempt)?, hash, attempt)?,
+            None => Outcome::Failure,
+        };
+        match outcome {
+            Outcome::Success => report.succeeded.push(number),
+            Outcome::NormalisationMismatch => {
+                report.failed.push(number);
+                report.normalisation_mismatches.push(number);
+            }
+            Outcome::Failure => report.failed.push(number),
+        }
+        let failures = consecutive_failures.entry(user).or_insert(0);
+        let accepted = outcome == Outcome::Success
+            || lockout.normalised && outcome == Outcome::NormalisationMismatch;
+        *failures = if accepted { 0 } else { *failures + 1 };
+        if lockout.threshold > 0 && *failures >= lockout.threshold {
+            report.locked_out_at = Some(number);
+        }
+    }
+    Ok(reports.into_values().collect())
+}
+
+/// one JSON object per line
+pub fn to_json_lines(reports: &[UserReport]) -> String {
+    reports
+        .iter()
+        .map(|report| serde_json::to_string(report).unwrap() + "\n")
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit() {
+        let ann = bcrypt::hash("café", 4).unwrap();
+        let bob = bcrypt::hash("pw", 4).unwrap();
+        let hash_db = HashMap::from([("ann", ann.as_str()), ("bob", bob.as_str())]);
+        let attempts = [
+            ("ann", "cafe\u{301}"),
+            ("bob", "nope"),
+            ("ann", "café"),
+            ("bob", "nope"),
+            ("carl", "x"),
+            ("bob", "nope"),
+            ("bob", "pw"),
+        ];
+        let reports = audit(
+            &hash_db,
+            &attempts,
+            &Verifier::default(),
+            &mut Cache::in_memory(),
+            Lockout::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            reports[0],
+            UserReport {
+                user: "ann".to_owned(),
+                attempts: 2,
+                succeeded: vec![3],
+                failed: vec![1],
+                normalisation_mismatches: vec![1],
+                locked_out_at: None,
+                blocked: vec![],
+            }
+        );
+        assert_eq!(reports[1].locked_out_at, Some(6));
+        assert_eq!(reports[1].attempts, 4);
+        assert_eq!(reports[1].failed, [2, 4, 6]);
+        assert!(reports[1].succeeded.is_empty());
+        assert_eq!(reports[1].blocked, [7]);
+        assert_eq!(reports[2].failed, [5]);
+    }
+
+    #[test]
+    fn test_normalised_lockout() {
+        let hash = bcrypt::hash("é", 4).unwrap();
+        let hash_db = HashMap::from([("ann", hash.as_str())]);
+        let attempts = [("ann", "e\u{301}"); 3];
+        let mut cache = Cache::in_memory();
+        let verifier = Verifier::default();
+        let strict = audit(
+            &hash_db,
+            &attempts,
+            &verifier,
+            &mut cache,
+            Lockout::default(),
+        )
+        .unwrap();
+        assert_eq!(strict[0].locked_out_at, Some(3));
+        let lockout = Lockout {
+            normalised: true,
+            ..Lockout::default()
+        };
+        let lenient = audit(&hash_db, &attempts, &verifier, &mut cache, lockout).unwrap();
+        assert_eq!(lenient[0].locked_out_at, None);
+        assert_eq!(lenient[0].normalisation_mismatches, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_lockout_disabled() {
+        let hash = bcrypt::hash("pw", 4).unwrap();
+        let hash_db = HashMap::from([("bob", hash.as_str())]);
+        let attempts = [
+            ("bob", "pw"),
+            ("bob", "nope"),
+            ("bob", "nope"),
+            ("bob", "pw"),
+        ];
+        let lockout = Lockout {
+            threshold: 0,
+            ..Lockout::default()
+        };
+        let verifier = Verifier::default();
+        let reports = audit(
+            &hash_db,
+            &attempts,
+            &verifier,
+            &mut Cache::in_memory(),
+            lockout,
+        )
+        .unwrap();
+        assert_eq!(reports[0].locked_out_at, None);
+        assert!(reports[0].blocked.is_empty());
+        assert_eq!(reports[0].succeeded, [1, 4]);
+        assert_eq!(reports[0].failed, [2, 3]);
+    }
+
+    #[test]
+    fn test_to_json_lines() {
+        let reports = [
+            UserReport {
+                user: "ann".to_owned(),
+                attempts: 1,
+                succeeded: vec![1],
+                ..UserReport::default()
+            },
+            UserReport {
+                user: "bob".to_owned(),
+                ..UserReport::default()
+            },
+        ];
+        let json = to_json_lines(&reports);
+        assert_eq!(json.lines().count(), 2);
+        assert!(json.starts_with(
+            r#"{"user":"ann","attempts":1,"succeeded":[1],"failed":[],"normalisation_mismatches":[],"locked_out_at":null,"blocked":[]}"#
+        ));
+    }
+}
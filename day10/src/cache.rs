@@ -0,0 +1,342 @@
+use crate::hash::HashError;
+use crate::verify::{Form, Verifier};
+use password_hash::rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+const HEADER: &str = "verification-cache v1";
+
+#[derive(Debug)]
+pub enum CacheError {
+    Io(io::Error),
+    Hash(HashError),
+    Corrupt(usize),
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Hash(err) => write!(f, "{err}"),
+            Self::Corrupt(line) => write!(f, "corrupt cache entry on line {line}"),
+        }
+    }
+}
+
+impl std::error::Error for CacheError {}
+
+impl From<io::Error> for CacheError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<HashError> for CacheError {
+    fn from(err: HashError) -> Self {
+        Self::Hash(err)
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn form_name(form: Option<Form>) -> &'static str {
+    match form {
+        Some(Form::Nfc) => "nfc",
+        Some(Form::Nfd) => "nfd",
+        Some(Form::Nfkc) => "nfkc",
+        Some(Form::Mixed) => "mixed",
+        None => "none",
+    }
+}
+
+fn parse_form(name: &str) -> Option<Option<Form>> {
+    match name {
+        "nfc" => Some(Some(Form::Nfc)),
+        "nfd" => Some(Some(Form::Nfd)),
+        "nfkc" => Some(Some(Form::Nfkc)),
+        "mixed" => Some(Some(Form::Mixed)),
+        "none" => Some(None),
+        _ => None,
+    }
+}
+
+/// remembers the outcome of each (user, stored hash, attempt) verification, optionally in an
+/// append-only file so that a later run doesn't repeat the work.
+///
+/// attempts are never written in the clear: entries are keyed by a SHA-256 of the user, hash and
+/// attempt under a random secret kept at the top of the file. that still makes a stolen cache
+/// file far cheaper to brute force than the stored hashes, so it should be protected like them
+#[derive(Debug)]
+pub struct Cache {
+    secret: [u8; 32],
+    entries: HashMap<String, Option<Form>>,
+    file: Option<File>,
+}
+
+impl Cache {
+    pub fn in_memory() -> Self {
+        let mut secret = [0; 32];
+        OsRng.fill_bytes(&mut secret);
+        Self {
+            secret,
+            entries: HashMap::new(),
+            file: None,
+        }
+    }
+
+    /// loads the cache at `path`, creating it if it doesn't exist. anything after the last
+    /// newline was torn by a crash mid-write, and is discarded
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CacheError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            let mut cache = Self::in_memory();
+            let mut file = File::create(path)?;
+            writeln!(file, "{HEADER}\n{}", hex(&cache.secret))?;
+            cache.file = Some(file);
+            return Ok(cache);
+        }
+        let contents = fs::read_to_string(path)?;
+        let complete = &contents[..contents.rfind('\n').map_or(0, |i| i + 1)];
+        let mut lines = complete.lines();
+        if lines.next() != Some(HEADER) {
+            return Err(CacheError::Corrupt(1));
+        }
+        let secret = lines.next().ok_or(CacheError::Corrupt(2))?;
+        let secret: Vec<u8> = (0..secret.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(secret.get(i..i + 2)?, 16).ok())
+            .collect::<Option<_>>()
+            .ok_or(CacheError::Corrupt(2))?;
+        let secret = secret.try_into().map_err(|_| CacheError::Corrupt(2))?;
+        let mut entries = HashMap::new();
+        for (i, line) in lines.enumerate() {
+            let (key, form) = line
+                .split_once(' ')
+                .and_then(|(key, form)| Some((key, parse_form(form)?)))
+                .ok_or(CacheError::Corrupt(i + 3))?;
+            entries.insert(key.to_owned(), form);
+        }
+        let file = OpenOptions::new().append(true).open(path)?;
+        file.set_len(complete.len() as u64)?;
+        Ok(Self {
+            secret,
+            entries,
+            file: Some(file),
+        })
+    }
+
+    fn key(&self, user: &str, hash: &str, attempt: &str) -> String {
+        let digest = Sha256::new()
+            .chain_update(self.secret)
+            .chain_update(user)
+            .chain_update([0])
+            .chain_update(hash)
+            .chain_update([0])
+            .chain_update(attempt)
+            .finalize();
+        hex(&digest)
+    }
+
+    pub fn get(&self, user: &str, hash: &str, attempt: &str) -> Option<Option<Form>> {
+        self.entries.get(&self.key(user, hash, attempt)).copied()
+    }
+
+    pub fn insert(
+        &mut self,
+        user: &str,
+        hash: &str,
+        attempt: &str,
+        form: Option<Form>,
+    ) -> io::Result<()> {
+        let key = self.key(user, hash, attempt);
+        if let Some(file) = &mut self.file {
+            // a single write, so a crash can only tear the line at its end
+            file.write_all(format!("{key} {}\n", form_name(form)).as_bytes())?;
+        }
+        self.entries.insert(key, form);
+        Ok(())
+    }
+
+    /// the form of `attempt` that matched `hash`, only running the verifier on a cache miss
+    pub fn verify(
+        &mut self,
+        verifier: &Verifier,
+        user: &str,
+        hash: &str,
+        attempt: &str,
+    ) -> Result<Option<Form>, CacheError> {
+        if let Some(form) = self.get(user, hash, attempt) {
+            return Ok(form);
+        }
+        let verification = verifier.verify(hash, attempt)?;
+        let form = verification.matched.map(|candidate| candidate.form);
+        self.insert(user, hash, attempt, form)?;
+        Ok(form)
+    }
+}
+
+/// how far through a batch a previous run got
+#[derive(Debug)]
+pub struct Checkpoint {
+    path: PathBuf,
+    pub processed: usize,
+}
+
+impl Checkpoint {
+    /// reads the checkpoint at `path`, starting from zero if there isn't one
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let processed = match fs::read_to_string(&path) {
+            Ok(contents) => contents
+                .trim()
+                .parse()
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid checkpoint"))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => 0,
+            Err(err) => return Err(err),
+        };
+        Ok(Self { path, processed })
+    }
+
+    /// records progress by writing a temporary file and renaming it over the old one, so a crash
+    /// never leaves a half-written checkpoint
+    pub fn save(&mut self, processed: usize) -> io::Result<()> {
+        let temp = self.path.with_extension("tmp");
+        fs::write(&temp, format!("{processed}\n"))?;
+        fs::rename(&temp, &self.path)?;
+        self.processed = processed;
+        Ok(())
+    }
+}
+
+/// a login attempt to be verified against a user's stored hash
+#[derive(Debug, Clone, Copy)]
+pub struct Job<'a> {
+    pub user: &'a str,
+    pub hash: &'a str,
+    pub attempt: &'a str,
+}
+
+/// verifies `jobs` in order through `cache`, saving `checkpoint` every `every` jobs, and returns
+/// the results of those it ran. jobs before the checkpoint were done by an earlier run over the
+/// same jobs, so are skipped
+pub fn run_batch(
+    jobs: &[Job],
+    verifier: &Verifier,
+    cache: &mut Cache,
+    checkpoint: &mut Checkpoint,
+    every: usize,
+) -> Result<Vec<Option<Form>>, CacheError> {
+    let start = checkpoint.processed.min(jobs.len());
+    let mut results = Vec::with_capacity(jobs.len() - start);
+    for (i, job) in jobs.iter().enumerate().skip(start) {
+        results.push(cache.verify(verifier, job.user, job.hash, job.attempt)?);
+        if (i + 1) % every.max(1) == 0 {
+            checkpoint.save(i + 1)?;
+        }
+    }
+    if jobs.len() > checkpoint.processed {
+        checkpoint.save(jobs.len())?;
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("day10-{}-{name}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_cache_persists() {
+        let path = temp_path("cache");
+        let verifier = Verifier::default();
+        let hash = bcrypt::hash("e\u{301}t\u{e9}", 4).unwrap();
+        let mut cache = Cache::open(&path).unwrap();
+        assert_eq!(cache.get("ann", &hash, "été"), None);
+        assert_eq!(
+            cache.verify(&verifier, "ann", &hash, "été").unwrap(),
+            Some(Form::Mixed)
+        );
+        cache.verify(&verifier, "ann", &hash, "ete").unwrap();
+        drop(cache);
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("été"));
+        assert_eq!(contents.lines().count(), 4);
+
+        let cache = Cache::open(&path).unwrap();
+        assert_eq!(cache.get("ann", &hash, "été"), Some(Some(Form::Mixed)));
+        assert_eq!(cache.get("ann", &hash, "ete"), Some(None));
+        assert_eq!(cache.get("bob", &hash, "été"), None);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_torn_line_is_ignored() {
+        let path = temp_path("torn");
+        let mut cache = Cache::open(&path).unwrap();
+        cache.insert("ann", "h", "a", None).unwrap();
+        drop(cache);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "0123abc").unwrap();
+        drop(file);
+        let mut cache = Cache::open(&path).unwrap();
+        cache.insert("ann", "h", "b", Some(Form::Nfc)).unwrap();
+        drop(cache);
+        assert!(!fs::read_to_string(&path).unwrap().contains("0123abc"));
+        let cache = Cache::open(&path).unwrap();
+        assert_eq!(cache.get("ann", "h", "a"), Some(None));
+        assert_eq!(cache.get("ann", "h", "b"), Some(Some(Form::Nfc)));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_resume_batch() {
+        let cache_path = temp_path("batch-cache");
+        let checkpoint_path = temp_path("batch-checkpoint");
+        let verifier = Verifier::default();
+        let hash = bcrypt::hash("pässword", 4).unwrap();
+        let jobs: Vec<Job> = ["pässword", "password", "pa\u{308}ssword"]
+            .iter()
+            .map(|attempt| Job {
+                user: "ann",
+                hash: &hash,
+                attempt,
+            })
+            .collect();
+
+        let mut cache = Cache::open(&cache_path).unwrap();
+        let mut checkpoint = Checkpoint::open(&checkpoint_path).unwrap();
+        assert_eq!(checkpoint.processed, 0);
+        run_batch(&jobs[..2], &verifier, &mut cache, &mut checkpoint, 1).unwrap();
+        drop(cache);
+
+        let mut cache = Cache::open(&cache_path).unwrap();
+        let mut checkpoint = Checkpoint::open(&checkpoint_path).unwrap();
+        assert_eq!(checkpoint.processed, 2);
+        assert_eq!(cache.get("ann", &hash, "password"), Some(None));
+        let results = run_batch(&jobs, &verifier, &mut cache, &mut checkpoint, 1).unwrap();
+        assert_eq!(results, [Some(Form::Nfc)]);
+        assert_eq!(Checkpoint::open(&checkpoint_path).unwrap().processed, 3);
+
+        // jobs before the checkpoint aren't verified again, even with their entries gone
+        let mut empty = Cache::in_memory();
+        let mut checkpoint = Checkpoint::open(&checkpoint_path).unwrap();
+        checkpoint.save(1).unwrap();
+        let results = run_batch(&jobs, &verifier, &mut empty, &mut checkpoint, 1).unwrap();
+        assert_eq!(results, [None, Some(Form::Nfc)]);
+        assert_eq!(empty.get("ann", &hash, "pässword"), None);
+        fs::remove_file(&cache_path).unwrap();
+        fs::remove_file(&checkpoint_path).unwrap();
+    }
+}
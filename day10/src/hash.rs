@@ -0,0 +1,273 @@
+use argon2::Argon2;
+use bcrypt::{BcryptError, HashParts};
+use password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng};
+use pbkdf2::Pbkdf2;
+use scrypt::Scrypt;
+use sha1::{Digest, Sha1};
+use std::fmt;
+
+/// a password hashing scheme, recognised from the prefix of a stored hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// modular crypt format, `$2a$`, `$2b$`, `$2x$` or `$2y$`
+    Bcrypt,
+    /// PHC format, `$argon2id$`
+    Argon2id,
+    /// PHC format, `$scrypt$`
+    Scrypt,
+    /// PHC format, `$pbkdf2-sha256$`
+    Pbkdf2Sha256,
+    /// `sha1$<salt>$<hex digest>`, the digest being of the salt followed by the password
+    SaltedSha1,
+}
+
+impl Algorithm {
+    pub fn detect(hash: &str) -> Option<Self> {
+        const PREFIXES: &[(&str, Algorithm)] = &[
+            ("$2a$", Algorithm::Bcrypt),
+            ("$2b$", Algorithm::Bcrypt),
+            ("$2x$", Algorithm::Bcrypt),
+            ("$2y$", Algorithm::Bcrypt),
+            ("$argon2id$", Algorithm::Argon2id),
+            ("$scrypt$", Algorithm::Scrypt),
+            ("$pbkdf2-sha256$", Algorithm::Pbkdf2Sha256),
+            ("sha1$", Algorithm::SaltedSha1),
+        ];
+        PREFIXES
+            .iter()
+            .find(|(prefix, _)| hash.starts_with(prefix))
+            .map(|&(_, algorithm)| algorithm)
+    }
+}
+
+#[derive(Debug)]
+pub enum HashError {
+    UnknownAlgorithm,
+    Malformed,
+    Bcrypt(BcryptError),
+    Phc(password_hash::Error),
+}
+
+impl fmt::Display for HashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownAlgorithm => f.write_str("unrecognised hash format"),
+            Self::Malformed => f.write_str("malformed hash"),
+            Self::Bcrypt(err) => write!(f, "bcrypt: {err}"),
+            Self::Phc(err) => write!(f, "PHC: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for HashError {}
+
+impl From<BcryptError> for HashError {
+    fn from(err: BcryptError) -> Self {
+        Self::Bcrypt(err)
+    }
+}
+
+impl From<password_hash::Error> for HashError {
+    fn from(err: password_hash::Error) -> Self {
+        Self::Phc(err)
+    }
+}
+
+fn sha1_hex(salt: &str, password: &str) -> String {
+    let digest = Sha1::new()
+        .chain_update(salt)
+        .chain_update(password)
+        .finalize();
+    digest.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// compares without stopping at the first difference, so timing doesn't leak the digest
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn verify_phc(
+    password: &str,
+    hash: &str,
+    verifier: &dyn PasswordVerifier,
+) -> Result<bool, HashError> {
+    let parsed = PasswordHash::new(hash)?;
+    match verifier.verify_password(password.as_bytes(), &parsed) {
+        Ok(()) => Ok(true),
+        Err(password_hash::Error::Password) => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// checks `password` against `hash` using whichever algorithm made it
+pub fn verify(password: &str, hash: &str) -> Result<bool, HashError> {
+    match Algorithm::detect(hash).ok_or(HashError::UnknownAlgorithm)? {
+        Algorithm::Bcrypt => Ok(bcrypt::verify(password, hash)?),
+        Algorithm::Argon2id => verify_phc(password, hash, &Argon2::default()),
+        Algorithm::Scrypt => verify_phc(password, hash, &Scrypt),
+        Algorithm::Pbkdf2Sha256 => verify_phc(password, hash, &Pbkdf2),
+        Algorithm::SaltedSha1 => {
+            let mut fields = hash.split('$').skip(1);
+            let (Some(salt), Some(digest), None) = (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(HashError::Malformed);
+            };
+            Ok(constant_time_eq(
+                sha1_hex(salt, password).as_bytes(),
+                digest.to_ascii_lowercase().as_bytes(),
+            ))
+        }
+    }
+}
+
+/// hashes `password` afresh with the algorithm and parameters of `hash`. legacy salted SHA-1
+/// hashes are upgraded to argon2id with its default parameters
+pub fn rehash(password: &str, hash: &str) -> Result<String, HashError> {
+    let salt = SaltString::generate(&mut OsRng);
+    let password = password.as_bytes();
+    let rehashed = match Algorithm::detect(hash).ok_or(HashError::UnknownAlgorithm)? {
+        Algorithm::Bcrypt => {
+            let cost = hash.parse::<HashParts>()?.get_cost();
+            return Ok(bcrypt::hash(password, cost)?);
+        }
+        Algorithm::Argon2id => {
+            let params = argon2::Params::try_from(&PasswordHash::new(hash)?)?;
+            Argon2::default().hash_password_customized(password, None, None, params, &salt)?
+        }
+        Algorithm::Scrypt => {
+            let params = scrypt::Params::try_from(&PasswordHash::new(hash)?)?;
+            Scrypt.hash_password_customized(password, None, None, params, &salt)?
+        }
+        Algorithm::Pbkdf2Sha256 => {
+            let parsed = PasswordHash::new(hash)?;
+            let params = pbkdf2::Params::try_from(&parsed)?;
+            Pbkdf2.hash_password_customized(
+                password,
+                Some(parsed.algorithm),
+                None,
+                params,
+                &salt,
+            )?
+        }
+        Algorithm::SaltedSha1 => Argon2::default().hash_password(password, &salt)?,
+    };
+    Ok(rehashed.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argon2_hash(password: &str) -> String {
+        let params = argon2::Params::new(64, 1, 1, None).unwrap();
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+            .hash_password(password.as_bytes(), &salt)
+            .unwrap()
+            .to_string()
+    }
+
+    fn scrypt_hash(password: &str) -> String {
+        let params = scrypt::Params::new(4, 8, 1, 32).unwrap();
+        let salt = SaltString::generate(&mut OsRng);
+        Scrypt
+            .hash_password_customized(password.as_bytes(), None, None, params, &salt)
+            .unwrap()
+            .to_string()
+    }
+
+    fn pbkdf2_hash(password: &str) -> String {
+        let params = pbkdf2::Params {
+            rounds: 1000,
+            output_length: 32,
+        };
+        let salt = SaltString::generate(&mut OsRng);
+        Pbkdf2
+            .hash_password_customized(
+                password.as_bytes(),
+                Some(pbkdf2::Algorithm::Pbkdf2Sha256.ident()),
+                None,
+                params,
+                &salt,
+            )
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            Algorithm::detect("$2b$07$0EBrxS4iHy/aHAhqbX/ao.n7305WlMoEpHd42aGKsG21wlktUQtNu"),
+            Some(Algorithm::Bcrypt)
+        );
+        assert_eq!(
+            Algorithm::detect(&argon2_hash("x")),
+            Some(Algorithm::Argon2id)
+        );
+        assert_eq!(
+            Algorithm::detect(&scrypt_hash("x")),
+            Some(Algorithm::Scrypt)
+        );
+        assert_eq!(
+            Algorithm::detect(&pbkdf2_hash("x")),
+            Some(Algorithm::Pbkdf2Sha256)
+        );
+        assert_eq!(
+            Algorithm::detect("sha1$salt$0123"),
+            Some(Algorithm::SaltedSha1)
+        );
+        assert_eq!(Algorithm::detect("$argon2i$v=19$..."), None);
+        assert_eq!(Algorithm::detect("md5$abc"), None);
+    }
+
+    #[test]
+    fn test_verify() {
+        let hashes = [
+            bcrypt::hash("tëst", 4).unwrap(),
+            argon2_hash("tëst"),
+            scrypt_hash("tëst"),
+            pbkdf2_hash("tëst"),
+            // Django's legacy salted SHA-1 of "tëst" with salt "abc"
+            format!("sha1$abc${}", sha1_hex("abc", "tëst")),
+        ];
+        for hash in &hashes {
+            assert!(verify("tëst", hash).unwrap(), "{hash}");
+            assert!(!verify("test", hash).unwrap(), "{hash}");
+        }
+        assert!(matches!(
+            verify("tëst", "plain"),
+            Err(HashError::UnknownAlgorithm)
+        ));
+        assert!(matches!(
+            verify("tëst", "sha1$abc"),
+            Err(HashError::Malformed)
+        ));
+        assert!(verify("tëst", "$scrypt$ln=x,r=8,p=1$c2FsdA$aGFzaA").is_err());
+    }
+
+    #[test]
+    fn test_sha1_hex() {
+        // sha1("saltpassword")
+        assert_eq!(
+            sha1_hex("salt", "password"),
+            "59b3e8d637cf97edbe2384cf59cb7453dfe30789"
+        );
+    }
+
+    #[test]
+    fn test_rehash() {
+        for hash in [
+            bcrypt::hash("old", 4).unwrap(),
+            argon2_hash("old"),
+            scrypt_hash("old"),
+            pbkdf2_hash("old"),
+        ] {
+            let rehashed = rehash("new", &hash).unwrap();
+            assert_eq!(Algorithm::detect(&rehashed), Algorithm::detect(&hash));
+            assert_ne!(rehashed, hash);
+            assert!(verify("new", &rehashed).unwrap());
+        }
+        let rehashed = rehash("new", "sha1$abc$0123").unwrap();
+        assert_eq!(Algorithm::detect(&rehashed), Some(Algorithm::Argon2id));
+    }
+}
@@ -0,0 +1,4 @@
+pub mod audit;
+pub mod cache;
+pub mod hash;
+pub mod verify;
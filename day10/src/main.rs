@@ -1,5 +1,7 @@
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
-use std::{collections::HashMap, fs};
+use day10::audit::{self, Lockout};
+use day10::cache::{self, Cache, Checkpoint, Job};
+use day10::verify::Verifier;
+use std::{collections::HashMap, env, fs};
 use unicode_normalization::UnicodeNormalization;
 
 fn parse(input: &str) -> (HashMap<&str, &str>, Vec<(&str, &str)>) {
@@ -16,50 +18,109 @@ fn parse(input: &str) -> (HashMap<&str, &str>, Vec<(&str, &str)>) {
     )
 }
 
-fn decompositions(composed: &str) -> Vec<String> {
-    let mut permutations = vec![String::new()];
-    for c in composed.chars() {
-        let d = c.nfd().to_string();
-        if d.chars().count() == 1 {
-            for perm in &mut permutations {
-                perm.push(c);
-            }
-        } else {
-            permutations = permutations
-                .into_iter()
-                .flat_map(|perm| [perm.clone() + &c.to_string(), perm + &d])
-                .collect();
-        }
-    }
-    permutations
+fn solution(input: &str) -> u32 {
+    solution_with_cache(input, &mut Cache::in_memory())
 }
 
-fn solution(input: &str) -> u32 {
+fn solution_with_cache(input: &str, cache: &mut Cache) -> u32 {
     let (hash_db, attempts) = parse(input);
+    let verifier = Verifier::default();
     let mut valid = 0;
     let mut password_cache = HashMap::new();
     for (user, password) in attempts {
         let hash = hash_db.get(user).unwrap();
         let composed = password.nfc().to_string();
         if !password_cache.contains_key(user)
-            && decompositions(&composed)
-                .par_iter()
-                .any(|perm| bcrypt::verify(perm, hash).unwrap())
+            && cache
+                .verify(&verifier, user, hash, password)
+                .unwrap()
+                .is_some()
         {
             password_cache.insert(user, composed.clone());
         }
-        if let Some(correct) = password_cache.get(user) {
-            if &composed == correct {
-                valid += 1;
-            }
+        if let Some(correct) = password_cache.get(user)
+            && &composed == correct
+        {
+            valid += 1;
         }
     }
     valid
 }
 
+const USAGE: &str = "usage: day10 [--cache PATH] | day10 audit [--cache PATH] [THRESHOLD] | \
+                     day10 batch CACHE CHECKPOINT [EVERY]";
+
+/// `batch CACHE CHECKPOINT [EVERY]` verifies every login attempt into the cache at CACHE,
+/// recording in CHECKPOINT every EVERY attempts how far it got, so a run that was interrupted
+/// carries on where it stopped. the answer is then worked out from the cache
+fn batch(input: &str, cache_path: &str, checkpoint_path: &str, every: usize) -> u32 {
+    let (hash_db, attempts) = parse(input);
+    let jobs: Vec<Job> = attempts
+        .iter()
+        .map(|&(user, attempt)| Job {
+            user,
+            hash: hash_db.get(user).unwrap(),
+            attempt,
+        })
+        .collect();
+    let mut cache = Cache::open(cache_path).unwrap();
+    let mut checkpoint = Checkpoint::open(checkpoint_path).unwrap();
+    cache::run_batch(
+        &jobs,
+        &Verifier::default(),
+        &mut cache,
+        &mut checkpoint,
+        every,
+    )
+    .unwrap();
+    solution_with_cache(input, &mut cache)
+}
+
+/// `day10 [--cache PATH]` keeps verification results in PATH so a re-run skips the bcrypt work.
+/// `day10 audit [--cache PATH] [THRESHOLD]` prints a JSON line per user instead of the answer,
+/// simulating a lockout after THRESHOLD consecutive failures, or none if it's 0. `day10 batch`
+/// verifies everything with checkpoints, for inputs too big to redo after a crash
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
     let input = fs::read_to_string("input").unwrap();
-    let answer = solution(&input);
+    let answer = match &args[..] {
+        [command, rest @ ..] if command == "audit" => {
+            let (mut cache, rest) = match rest {
+                [flag, path, rest @ ..] if flag == "--cache" => (Cache::open(path).unwrap(), rest),
+                rest => (Cache::in_memory(), rest),
+            };
+            let mut lockout = Lockout::default();
+            match rest {
+                [] => {}
+                [threshold] => lockout.threshold = threshold.parse().unwrap(),
+                _ => panic!("{USAGE}"),
+            }
+            let (hash_db, attempts) = parse(&input);
+            let reports = audit::audit(
+                &hash_db,
+                &attempts,
+                &Verifier::default(),
+                &mut cache,
+                lockout,
+            )
+            .unwrap();
+            print!("{}", audit::to_json_lines(&reports));
+            return;
+        }
+        [command, cache, checkpoint, rest @ ..] if command == "batch" => {
+            let every = match rest {
+                [] => 1000,
+                [every] => every.parse().unwrap(),
+                _ => panic!("{USAGE}"),
+            };
+            batch(&input, cache, checkpoint, every)
+        }
+        [flag, path] if flag == "--cache" => {
+            solution_with_cache(&input, &mut Cache::open(path).unwrap())
+        }
+        [] => solution(&input),
+        _ => panic!("{USAGE}"),
+    };
     println!("{answer}");
 }
 
@@ -72,4 +133,23 @@ mod tests {
         let input = fs::read_to_string("test-input").unwrap();
         assert_eq!(solution(&input), 4);
     }
+
+    #[test]
+    fn test_batch_resumes() {
+        let input = fs::read_to_string("test-input").unwrap();
+        let path = |name: &str| {
+            let path = env::temp_dir().join(format!("day10-{}-main-{name}", std::process::id()));
+            path.to_str().unwrap().to_owned()
+        };
+        let (cache, checkpoint) = (path("cache"), path("checkpoint"));
+        let _ = fs::remove_file(&cache);
+        fs::write(&checkpoint, "2\n").unwrap();
+        assert_eq!(batch(&input, &cache, &checkpoint, 3), 4);
+        let (_, attempts) = parse(&input);
+        let processed = fs::read_to_string(&checkpoint).unwrap();
+        assert_eq!(processed.trim(), attempts.len().to_string());
+        assert_eq!(batch(&input, &cache, &checkpoint, 3), 4);
+        fs::remove_file(&cache).unwrap();
+        fs::remove_file(&checkpoint).unwrap();
+    }
 }
@@ -0,0 +1,225 @@
+use crate::hash::{self, HashError};
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use unicode_normalization::UnicodeNormalization;
+
+/// how many candidates are tried by default: every mixture of ten decomposable characters, NFC
+/// and NFD among them, and the NFKC form when compatibility forms are tried
+pub const DEFAULT_MAX_CANDIDATES: usize = (1 << 10) + 1;
+
+/// the normalisation a candidate was produced by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Form {
+    Nfc,
+    Nfd,
+    Nfkc,
+    /// some characters composed and others decomposed
+    Mixed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub form: Form,
+    pub text: String,
+}
+
+fn push_new(candidates: &mut Vec<Candidate>, form: Form, text: String) {
+    if candidates.iter().all(|c| c.text != text) {
+        candidates.push(Candidate { form, text });
+    }
+}
+
+/// the forms of `attempt` worth trying against a stored hash, most likely first: NFC, NFD, NFKC
+/// if `compatibility`, then mixtures with as few decomposed characters as possible. duplicates
+/// are skipped and at most `max` candidates are returned
+pub fn candidates(attempt: &str, max: usize, compatibility: bool) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    let composed: Vec<char> = attempt.nfc().collect();
+    push_new(&mut candidates, Form::Nfc, composed.iter().collect());
+    push_new(&mut candidates, Form::Nfd, attempt.nfd().collect());
+    if compatibility {
+        push_new(&mut candidates, Form::Nfkc, attempt.nfkc().collect());
+    }
+    let decomposable: Vec<usize> = (0..composed.len())
+        .filter(|&i| composed[i].nfd().nth(1).is_some())
+        .collect();
+    // mixtures are built by decomposing `size` of the decomposable characters at a time,
+    // stepping through each choice of positions in lexicographic order
+    for size in 1..decomposable.len() {
+        let mut chosen: Vec<usize> = (0..size).collect();
+        loop {
+            if candidates.len() >= max {
+                break;
+            }
+            let mut text = String::new();
+            let mut next = chosen.iter().map(|&i| decomposable[i]).peekable();
+            for (i, &c) in composed.iter().enumerate() {
+                if next.peek() == Some(&i) {
+                    next.next();
+                    text.extend(c.nfd());
+                } else {
+                    text.push(c);
+                }
+            }
+            push_new(&mut candidates, Form::Mixed, text);
+            let Some(k) = (0..size)
+                .rev()
+                .find(|&k| chosen[k] < decomposable.len() - size + k)
+            else {
+                break;
+            };
+            chosen[k] += 1;
+            for j in k + 1..size {
+                chosen[j] = chosen[j - 1] + 1;
+            }
+        }
+    }
+    candidates.truncate(max);
+    candidates
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verification {
+    /// the candidate that matched the stored hash, if any
+    pub matched: Option<Candidate>,
+    /// the number of candidates there were to try. they're hashed in parallel, so some after
+    /// the one that matched may have been hashed too, and some before it may not have been
+    pub candidates: usize,
+}
+
+impl Verification {
+    pub fn is_valid(&self) -> bool {
+        self.matched.is_some()
+    }
+
+    /// the password matched, but the stored hash wasn't made from its NFC form
+    pub fn needs_rehash(&self) -> bool {
+        self.matched
+            .as_ref()
+            .is_some_and(|candidate| candidate.form != Form::Nfc)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Verifier {
+    pub max_candidates: usize,
+    /// also try the NFKC form, which folds compatibility characters such as ligatures and
+    /// full-width letters. off by default, as that can accept what is really another password
+    pub compatibility: bool,
+}
+
+impl Default for Verifier {
+    fn default() -> Self {
+        Self {
+            max_candidates: DEFAULT_MAX_CANDIDATES,
+            compatibility: false,
+        }
+    }
+}
+
+impl Verifier {
+    /// checks the normalisation candidates of `attempt` against `hash` in parallel, reporting the
+    /// highest priority one that matches. any algorithm known to [`hash::verify`] can be used
+    pub fn verify(&self, hash: &str, attempt: &str) -> Result<Verification, HashError> {
+        let candidates = candidates(attempt, self.max_candidates, self.compatibility);
+        let matched = candidates
+            .par_iter()
+            .enumerate()
+            .find_map_first(|(i, candidate)| match hash::verify(&candidate.text, hash) {
+                Ok(true) => Some(Ok(i)),
+                Ok(false) => None,
+                Err(err) => Some(Err(err)),
+            })
+            .transpose()?;
+        Ok(Verification {
+            candidates: candidates.len(),
+            matched: matched.map(|i| candidates[i].clone()),
+        })
+    }
+
+    /// hashes the NFC form of `password` like `hash`, for replacing a hash that was made from
+    /// some other form
+    pub fn rehash(&self, hash: &str, password: &str) -> Result<String, HashError> {
+        hash::rehash(&password.nfc().collect::<String>(), hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(candidates: &[Candidate]) -> Vec<&str> {
+        candidates.iter().map(|c| c.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_candidates() {
+        let candidates = candidates("ée", 10, true);
+        assert_eq!(
+            candidates.iter().map(|c| c.form).collect::<Vec<_>>(),
+            [Form::Nfc, Form::Nfd]
+        );
+        assert_eq!(texts(&candidates), ["ée", "e\u{301}e"]);
+    }
+
+    #[test]
+    fn test_mixtures() {
+        let all = candidates("éüﬁ", 10, true);
+        assert_eq!(
+            texts(&all),
+            [
+                "éüﬁ",
+                "e\u{301}u\u{308}ﬁ",
+                "éüfi",
+                "e\u{301}üﬁ",
+                "éu\u{308}ﬁ"
+            ]
+        );
+        assert_eq!(all[4].form, Form::Mixed);
+        assert_eq!(candidates("aáäâ", 100, false).len(), 8);
+        assert_eq!(texts(&candidates("éüﬁ", 3, true)), texts(&all[..3]));
+        let canonical = candidates("éüﬁ", 10, false);
+        assert!(canonical.iter().all(|c| c.form != Form::Nfkc));
+        assert_eq!(canonical.len(), 4);
+    }
+
+    #[test]
+    fn test_default_cap() {
+        let ten = "áéíóúäëïöü";
+        assert_eq!(
+            candidates(ten, DEFAULT_MAX_CANDIDATES, false).len(),
+            1 << 10
+        );
+        let with_ligature = format!("{ten}ﬁ");
+        let all = candidates(&with_ligature, DEFAULT_MAX_CANDIDATES, true);
+        assert_eq!(all.len(), DEFAULT_MAX_CANDIDATES);
+        let last: String = format!("á{}ﬁ", ten.chars().skip(1).collect::<String>().nfd());
+        assert_eq!(all.last().unwrap().text, last);
+    }
+
+    #[test]
+    fn test_verify() {
+        let verifier = Verifier::default();
+        let hash = bcrypt::hash("u\u{308}ber", 4).unwrap();
+        let verification = verifier.verify(&hash, "über").unwrap();
+        assert_eq!(verification.matched.unwrap().form, Form::Nfd);
+        let verification = verifier.verify(&hash, "uber").unwrap();
+        assert!(!verification.is_valid());
+        assert_eq!(verification.candidates, 1);
+        assert!(verifier.verify("not a hash", "über").is_err());
+        let hash = format!("sha1$s${}", "5c0f4ac2e34fa2c4ea5a5fbc9d3ab0c48c5c9e8b");
+        assert!(!verifier.verify(&hash, "über").unwrap().is_valid());
+    }
+
+    #[test]
+    fn test_rehash() {
+        let verifier = Verifier::default();
+        let hash = bcrypt::hash("e\u{301}a\u{308}", 4).unwrap();
+        let verification = verifier.verify(&hash, "éä").unwrap();
+        assert!(verification.needs_rehash());
+        let rehashed = verifier.rehash(&hash, "éä").unwrap();
+        assert!(rehashed.starts_with("$2b$04$"));
+        let verification = verifier.verify(&rehashed, "e\u{301}ä").unwrap();
+        assert!(verification.is_valid());
+        assert!(!verification.needs_rehash());
+    }
+}
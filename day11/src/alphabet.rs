@@ -0,0 +1,315 @@
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+/// the letters of a script in order, with their capitals and any word-final forms
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alphabet {
+    letters: Vec<char>,
+    /// the capital of each letter, for scripts that have case
+    capitals: Vec<Option<char>>,
+    /// `(medial, final)` pairs of letters written differently at the end of a word
+    finals: Vec<(char, char)>,
+    /// `(letter, variant)` pairs of alternative glyphs read as the letter, like Greek ϐ for β
+    variants: Vec<(char, char)>,
+}
+
+/// the single character capital of `c`, if it has one
+fn capital(c: char) -> Option<char> {
+    let mut upper = c.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(upper), None) if upper != c => Some(upper),
+        _ => None,
+    }
+}
+
+impl Alphabet {
+    /// an alphabet of `letters` in order, taking capitals from Unicode's case mappings
+    pub fn new(letters: &str) -> Self {
+        let letters: Vec<char> = letters.chars().collect();
+        let capitals = letters.iter().map(|&c| capital(c)).collect();
+        Self {
+            letters,
+            capitals,
+            finals: Vec::new(),
+            variants: Vec::new(),
+        }
+    }
+
+    /// inserts `letter` straight after `after`, for optional letters such as archaic ones
+    ///
+    /// # Panics
+    ///
+    /// if `after` isn't a letter of the alphabet
+    pub fn with_letter(mut self, after: char, letter: char) -> Self {
+        let i = self.letters.iter().position(|&c| c == after).unwrap() + 1;
+        self.letters.insert(i, letter);
+        self.capitals.insert(i, capital(letter));
+        self
+    }
+
+    pub fn with_finals(mut self, finals: &[(char, char)]) -> Self {
+        self.finals.extend_from_slice(finals);
+        self
+    }
+
+    /// `(variant, letter)` pairs of glyphs to read as the letter
+    pub fn with_variants(mut self, variants: &[(char, char)]) -> Self {
+        self.variants
+            .extend(variants.iter().map(|&(variant, letter)| (letter, variant)));
+        self
+    }
+
+    pub fn latin() -> Self {
+        Self::new("abcdefghijklmnopqrstuvwxyz")
+    }
+
+    /// the 24 letters of classical and modern Greek. accented and polytonic letters are read as
+    /// their base letter, and the symbol forms of letters as the letters themselves
+    pub fn greek() -> Self {
+        Self::new("αβγδεζηθικλμνξοπρστυφχψω")
+            .with_finals(&[('σ', 'ς')])
+            .with_variants(&[
+                ('ϐ', 'β'),
+                ('ϵ', 'ε'),
+                ('ϑ', 'θ'),
+                ('ϴ', 'Θ'),
+                ('ϰ', 'κ'),
+                ('ϖ', 'π'),
+                ('ϱ', 'ρ'),
+                ('ϲ', 'σ'),
+                ('Ϲ', 'Σ'),
+                ('ϕ', 'φ'),
+            ])
+    }
+
+    /// Greek with digamma, koppa and sampi in their places as numerals
+    pub fn archaic_greek() -> Self {
+        Self::greek()
+            .with_letter('ε', 'ϝ')
+            .with_letter('π', 'ϙ')
+            .with_letter('ω', 'ϡ')
+    }
+
+    /// the Russian alphabet, in which ё and й are letters rather than е and и with diacritics
+    pub fn cyrillic() -> Self {
+        Self::new("абвгдеёжзийклмнопрстуфхцчшщъыьэюя")
+    }
+
+    pub fn hebrew() -> Self {
+        Self::new("אבגדהוזחטיכלמנסעפצקרשת").with_finals(&[
+            ('כ', 'ך'),
+            ('מ', 'ם'),
+            ('נ', 'ן'),
+            ('פ', 'ף'),
+            ('צ', 'ץ'),
+        ])
+    }
+
+    pub fn armenian() -> Self {
+        Self::new("աբգդեզէըթժիլխծկհձղճմյնշոչպջռսվտրցւփքօֆ")
+    }
+
+    /// the 33 letters of modern Mkhedruli
+    pub fn georgian() -> Self {
+        Self::new("აბგდევზთიკლმნოპჟრსტუფქღყშჩცძწჭხჯჰ")
+    }
+
+    /// one of the built-in alphabets: latin, greek, archaic-greek, cyrillic, hebrew, armenian or
+    /// georgian
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "latin" => Some(Self::latin()),
+            "greek" => Some(Self::greek()),
+            "archaic-greek" => Some(Self::archaic_greek()),
+            "cyrillic" => Some(Self::cyrillic()),
+            "hebrew" => Some(Self::hebrew()),
+            "armenian" => Some(Self::armenian()),
+            "georgian" => Some(Self::georgian()),
+            _ => None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.letters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.letters.is_empty()
+    }
+
+    /// the position of `c` in the alphabet and whether it is a capital. final forms and variants
+    /// count as the letter they stand for
+    pub fn index(&self, c: char) -> Option<(usize, bool)> {
+        let c = self
+            .finals
+            .iter()
+            .chain(&self.variants)
+            .find(|&&(_, form)| form == c)
+            .map_or(c, |&(letter, _)| letter);
+        if let Some(i) = self.letters.iter().position(|&a| a == c) {
+            return Some((i, false));
+        }
+        let i = self.capitals.iter().position(|&a| a == Some(c))?;
+        Some((i, true))
+    }
+
+    /// the letter at `index`, as a capital if asked and the script has one
+    pub fn letter(&self, index: usize, capital: bool) -> char {
+        match self.capitals[index] {
+            Some(upper) if capital => upper,
+            _ => self.letters[index],
+        }
+    }
+
+    fn final_form(&self, c: char) -> char {
+        self.finals
+            .iter()
+            .find(|&&(medial, _)| medial == c)
+            .map_or(c, |&(_, last)| last)
+    }
+
+    /// the index of every letter of `text`, ignoring case and diacritics, with `None` for each
+    /// character that isn't a letter of the alphabet. combining marks are skipped
+    pub fn indices(&self, text: &str) -> Vec<Option<usize>> {
+        text.nfc()
+            .filter(|&c| !is_combining_mark(c))
+            .map(|c| {
+                let base = self
+                    .index(c)
+                    .or_else(|| self.index(std::iter::once(c).nfd().next()?));
+                base.map(|(index, _)| index)
+            })
+            .collect()
+    }
+
+    /// replaces each letter of `text` with the letter at `f(index)`, keeping its case and any
+    /// diacritics: a character that isn't itself a letter is decomposed and its base letter
+    /// replaced. letters with a final form take it when no further letter follows
+    pub fn substitute(&self, text: &str, mut f: impl FnMut(usize) -> usize) -> String {
+        let chars: Vec<char> = text.nfc().collect();
+        let mut substituted = String::new();
+        for (i, &c) in chars.iter().enumerate() {
+            let (base, marks) = match self.index(c) {
+                Some(letter) => (Some(letter), String::new()),
+                None => {
+                    let mut decomposed = std::iter::once(c).nfd();
+                    let first = decomposed.next().unwrap();
+                    let marks: String = decomposed.collect();
+                    match self.index(first) {
+                        Some(letter) if marks.chars().all(is_combining_mark) => {
+                            (Some(letter), marks)
+                        }
+                        _ => (None, String::new()),
+                    }
+                }
+            };
+            let Some((index, capital)) = base else {
+                substituted.push(c);
+                continue;
+            };
+            let mut letter = self.letter(f(index) % self.len(), capital);
+            let word_ends = !chars[i + 1..]
+                .iter()
+                .find(|&&next| !is_combining_mark(next))
+                .is_some_and(|next| next.is_alphabetic());
+            if word_ends {
+                letter = self.final_form(letter);
+            }
+            substituted.push(letter);
+            substituted.push_str(&marks);
+        }
+        substituted.nfc().collect()
+    }
+
+    /// shifts every letter of `text` `shift` places along the alphabet
+    pub fn rotate(&self, text: &str, shift: usize) -> String {
+        self.substitute(text, |i| i + shift)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate_greek() {
+        let greek = Alphabet::greek();
+        assert_eq!(
+            greek.rotate("σζμ γ' ωοωλδθαξλδμξρ οπξρδυζ οξκτλζσθρ Ξγτρρδτρ.", 1),
+            "την δ' απαμειβομενος προσεφη πολυμητις Οδυσσευς."
+        );
+        assert_eq!(
+            greek.rotate("αφτ κ' λαλψφτ ωπφχλρφτ δξησηρζαλψφτ φελο, Φκβωωλβ.", 18),
+            "τον δ' ετερον σκοπελον χθαμαλωτερον οψει, Οδυσσευ."
+        );
+    }
+
+    #[test]
+    fn test_rotate_scripts() {
+        assert_eq!(
+            Alphabet::latin().rotate("Hello, World!", 13),
+            "Uryyb, Jbeyq!"
+        );
+        let cyrillic = Alphabet::cyrillic();
+        assert_eq!(cyrillic.rotate("Ёж и йод", 1), "Жз й кпе");
+        assert_eq!(cyrillic.rotate("Жз й кпе", 32), "Ёж и йод");
+        assert_eq!(Alphabet::armenian().rotate("Հայ", 1), "Ձբն");
+        assert_eq!(Alphabet::georgian().rotate("საქართველო", 1), "ტბღბსიზვმპ");
+    }
+
+    #[test]
+    fn test_final_forms() {
+        let hebrew = Alphabet::hebrew();
+        // ם rotates to נ, which ends the word so is written ן
+        assert_eq!(hebrew.rotate("שלום", 1), "תמזן");
+        assert_eq!(hebrew.rotate("תמזן", 21), "שלום");
+        assert_eq!(hebrew.rotate("לכ", 1), "מל");
+        assert_eq!(Alphabet::greek().rotate("ρ σ ρα", 1), "ς τ σβ");
+    }
+
+    #[test]
+    fn test_polytonic() {
+        let greek = Alphabet::greek();
+        assert_eq!(
+            greek.rotate("ά ῆ ϊ ΐ", 1),
+            "β\u{301} θ\u{342} κ\u{308} κ\u{308}\u{301}"
+        );
+        let text = "ἄνδρα μοι ἔννεπε, Μοῦσα, πολύτροπον, ὃς μάλα πολλὰ πλάγχθη· ᾠδῇ";
+        for shift in 0..greek.len() {
+            let rotated = greek.rotate(text, shift);
+            assert_eq!(greek.rotate(&rotated, greek.len() - shift), text);
+        }
+        assert_eq!(greek.rotate("ἀρχή", 2), "γ\u{313}τωί");
+        assert_eq!(greek.rotate("ϐϑϲ Ϲ", 1), "γιτ Τ");
+    }
+
+    #[test]
+    fn test_archaic_greek() {
+        let archaic = Alphabet::archaic_greek();
+        assert_eq!(archaic.len(), 27);
+        assert_eq!(archaic.rotate("εϝζ πϙρ ωϡ", 1), "ϝζη ϙρς ϡα");
+        assert_eq!(archaic.rotate("Ϝ", 26), "Ε");
+        assert_eq!(Alphabet::greek().rotate("ϝ", 1), "ϝ");
+    }
+
+    #[test]
+    fn test_indices() {
+        assert_eq!(
+            Alphabet::greek().indices("Ὀδυς, ς"),
+            [Some(14), Some(3), Some(19), Some(17), None, None, Some(17)]
+        );
+    }
+
+    #[test]
+    fn test_diacritics() {
+        let latin = Alphabet::latin();
+        assert_eq!(latin.rotate("Café Ñu", 1), "Dbgf\u{301} Õv");
+        assert_eq!(latin.rotate("Dbgf\u{301} Õv", 25), "Café Ñu");
+        assert_eq!(latin.rotate("ß", 1), "ß");
+        // niqqud are kept on the rotated letter
+        assert_eq!(
+            Alphabet::hebrew().rotate("ב\u{5bc}\u{5b8}", 1),
+            "ג\u{5b8}\u{5bc}"
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use crate::alphabet::Alphabet;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CipherError {
+    /// the Vigenère keyword has no letters of the alphabet
+    EmptyKey,
+    /// the affine multiplier shares a factor with the length of the alphabet
+    NotInvertible,
+}
+
+impl fmt::Display for CipherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyKey => f.write_str("keyword has no letters of the alphabet"),
+            Self::NotInvertible => {
+                f.write_str("multiplier must be coprime with the length of the alphabet")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CipherError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mode {
+    Caesar(usize),
+    /// the shift for each letter in turn, taken from the letters of a keyword
+    Vigenere(Vec<usize>),
+    /// `a * x + b`, along with the inverse of `a`
+    Affine {
+        a: usize,
+        b: usize,
+        inverse: usize,
+    },
+    /// the alphabet reversed
+    Atbash,
+}
+
+/// a substitution cipher over the letters of an alphabet. case, diacritics, final forms and
+/// anything that isn't a letter are left as [`Alphabet::substitute`] leaves them
+#[derive(Debug, Clone)]
+pub struct Cipher {
+    alphabet: Alphabet,
+    mode: Mode,
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+impl Cipher {
+    pub fn caesar(alphabet: Alphabet, shift: usize) -> Self {
+        let shift = shift % alphabet.len();
+        Self {
+            alphabet,
+            mode: Mode::Caesar(shift),
+        }
+    }
+
+    /// a Vigenère cipher keyed by the letters of `keyword`, which must be in the same script
+    pub fn vigenere(alphabet: Alphabet, keyword: &str) -> Result<Self, CipherError> {
+        let shifts: Vec<usize> = alphabet.indices(keyword).into_iter().flatten().collect();
+        if shifts.is_empty() {
+            return Err(CipherError::EmptyKey);
+        }
+        Ok(Self {
+            alphabet,
+            mode: Mode::Vigenere(shifts),
+        })
+    }
+
+    pub fn affine(alphabet: Alphabet, a: usize, b: usize) -> Result<Self, CipherError> {
+        let n = alphabet.len();
+        let a = a % n;
+        if gcd(a, n) != 1 {
+            return Err(CipherError::NotInvertible);
+        }
+        let inverse = (1..n).find(|&x| a * x % n == 1).unwrap_or(1);
+        Ok(Self {
+            alphabet,
+            mode: Mode::Affine {
+                a,
+                b: b % n,
+                inverse,
+            },
+        })
+    }
+
+    pub fn atbash(alphabet: Alphabet) -> Self {
+        Self {
+            alphabet,
+            mode: Mode::Atbash,
+        }
+    }
+
+    pub fn mode(&self) -> &Mode {
+        &self.mode
+    }
+
+    fn apply(&self, text: &str, decrypt: bool) -> String {
+        let n = self.alphabet.len();
+        let mut position = 0;
+        self.alphabet.substitute(text, |x| match &self.mode {
+            Mode::Caesar(shift) if decrypt => x + n - shift,
+            Mode::Caesar(shift) => x + shift,
+            Mode::Vigenere(shifts) => {
+                let shift = shifts[position % shifts.len()];
+                position += 1;
+                if decrypt { x + n - shift } else { x + shift }
+            }
+            Mode::Affine { b, inverse, .. } if decrypt => inverse * (x + n - b),
+            Mode::Affine { a, b, .. } => a * x + b,
+            Mode::Atbash => n - 1 - x,
+        })
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> String {
+        self.apply(plaintext, false)
+    }
+
+    pub fn decrypt(&self, ciphertext: &str) -> String {
+        self.apply(ciphertext, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_caesar() {
+        let cipher = Cipher::caesar(Alphabet::greek(), 23);
+        let ciphertext = "σζμ γ' ωοωλδθαξλδμξρ οπξρδυζ οξκτλζσθρ Ξγτρρδτρ.";
+        let plaintext = "την δ' απαμειβομενος προσεφη πολυμητις Οδυσσευς.";
+        assert_eq!(cipher.encrypt(plaintext), ciphertext);
+        assert_eq!(cipher.decrypt(ciphertext), plaintext);
+    }
+
+    #[test]
+    fn test_vigenere() {
+        let latin = Cipher::vigenere(Alphabet::latin(), "LEMON").unwrap();
+        assert_eq!(latin.encrypt("attack at dawn"), "lxfopv ef rnhr");
+        assert_eq!(latin.decrypt("lxfopv ef rnhr"), "attack at dawn");
+        let cyrillic = Cipher::vigenere(Alphabet::cyrillic(), "ключ").unwrap();
+        let plaintext = "Съешь же ещё этих мягких французских булок";
+        assert_eq!(cyrillic.decrypt(&cyrillic.encrypt(plaintext)), plaintext);
+        assert_eq!(
+            Cipher::vigenere(Alphabet::greek(), "key").unwrap_err(),
+            CipherError::EmptyKey
+        );
+    }
+
+    #[test]
+    fn test_affine() {
+        let cipher = Cipher::affine(Alphabet::latin(), 5, 8).unwrap();
+        assert_eq!(cipher.encrypt("Affine cipher"), "Ihhwvc swfrcp");
+        assert_eq!(cipher.decrypt("Ihhwvc swfrcp"), "Affine cipher");
+        let hebrew = Cipher::affine(Alphabet::hebrew(), 3, 1).unwrap();
+        assert_eq!(hebrew.decrypt(&hebrew.encrypt("שלום עולם")), "שלום עולם");
+        assert_eq!(
+            Cipher::affine(Alphabet::greek(), 6, 1).unwrap_err(),
+            CipherError::NotInvertible
+        );
+    }
+
+    #[test]
+    fn test_atbash() {
+        let cipher = Cipher::atbash(Alphabet::latin());
+        assert_eq!(cipher.encrypt("Wizard"), "Draziw");
+        let hebrew = Cipher::atbash(Alphabet::hebrew());
+        // the classic example from Jeremiah: ששך for בבל
+        assert_eq!(hebrew.encrypt("בבל"), "ששך");
+        assert_eq!(hebrew.decrypt("ששך"), "בבל");
+    }
+}
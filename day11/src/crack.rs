@@ -0,0 +1,254 @@
+use crate::alphabet::Alphabet;
+
+/// letter and bigram log probabilities for one language, trained from sample text
+#[derive(Debug, Clone)]
+pub struct LanguageModel {
+    pub name: String,
+    pub alphabet: Alphabet,
+    unigrams: Vec<f64>,
+    /// `bigrams[a * n + b]` is the log probability of `b` following `a` within a word
+    bigrams: Vec<f64>,
+}
+
+impl LanguageModel {
+    /// counts the letters of `corpus` and the pairs of letters within its words, with add-one
+    /// smoothing so that unseen pairs are unlikely rather than impossible
+    pub fn train(name: &str, alphabet: Alphabet, corpus: &str) -> Self {
+        let n = alphabet.len();
+        let mut unigrams = vec![1.0; n];
+        let mut bigrams = vec![1.0; n * n];
+        let indices = alphabet.indices(corpus);
+        for (i, &index) in indices.iter().enumerate() {
+            let Some(b) = index else {
+                continue;
+            };
+            unigrams[b] += 1.0;
+            if let Some(&Some(a)) = i.checked_sub(1).and_then(|i| indices.get(i)) {
+                bigrams[a * n + b] += 1.0;
+            }
+        }
+        let total: f64 = unigrams.iter().sum();
+        let unigrams = unigrams.iter().map(|count| (count / total).ln()).collect();
+        for row in bigrams.chunks_mut(n) {
+            let total: f64 = row.iter().sum();
+            for count in row {
+                *count = (*count / total).ln();
+            }
+        }
+        Self {
+            name: name.to_owned(),
+            alphabet,
+            unigrams,
+            bigrams,
+        }
+    }
+
+    pub fn english() -> Self {
+        Self::train("English", Alphabet::latin(), corpora::ENGLISH)
+    }
+
+    /// trained on Homer, so suited to ancient as well as modern Greek
+    pub fn greek() -> Self {
+        Self::train("Greek", Alphabet::greek(), corpora::GREEK)
+    }
+
+    pub fn russian() -> Self {
+        Self::train("Russian", Alphabet::cyrillic(), corpora::RUSSIAN)
+    }
+
+    pub fn bundled() -> Vec<Self> {
+        vec![Self::english(), Self::greek(), Self::russian()]
+    }
+
+    /// the mean log probability of each letter of `text`, or `None` if it has no letters in
+    /// this language's alphabet
+    pub fn score(&self, text: &str) -> Option<f64> {
+        let n = self.alphabet.len();
+        let mut total = 0.0;
+        let mut letters = 0;
+        let mut previous = None;
+        for index in self.alphabet.indices(text) {
+            if let Some(b) = index {
+                total += match previous {
+                    Some(a) => self.bigrams[a * n + b],
+                    None => self.unigrams[b],
+                };
+                letters += 1;
+            }
+            previous = index;
+        }
+        (letters > 0).then(|| total / letters as f64)
+    }
+}
+
+/// how a shift was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Statistics,
+    Keyword,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crack {
+    pub language: String,
+    /// the rotation that turns the ciphertext into the plaintext
+    pub shift: usize,
+    pub plaintext: String,
+    pub method: Method,
+    /// how much better the best plaintext scored than the runner-up
+    pub margin: f64,
+}
+
+/// below this margin the statistics are too close to call, and keywords are searched for instead
+pub const DEFAULT_MIN_MARGIN: f64 = 0.25;
+
+/// finds the rotation of a ciphertext that reads most like one of its languages
+#[derive(Debug, Clone)]
+pub struct Cracker {
+    models: Vec<LanguageModel>,
+    keywords: Vec<String>,
+    pub min_margin: f64,
+}
+
+impl Cracker {
+    pub fn new(models: Vec<LanguageModel>) -> Self {
+        Self {
+            models,
+            keywords: Vec::new(),
+            min_margin: DEFAULT_MIN_MARGIN,
+        }
+    }
+
+    /// words expected in the plaintext, looked for under every shift when the statistics can't
+    /// decide and the most plausible plaintext has none of them
+    pub fn with_keywords(mut self, keywords: &[&str]) -> Self {
+        self.keywords
+            .extend(keywords.iter().map(|&keyword| keyword.to_owned()));
+        self
+    }
+
+    /// every language and shift under which `ciphertext` has letters, best first
+    fn ranked(&self, ciphertext: &str) -> Vec<(f64, Crack)> {
+        let mut ranked = Vec::new();
+        for model in &self.models {
+            for shift in 0..model.alphabet.len() {
+                let plaintext = model.alphabet.rotate(ciphertext, shift);
+                let Some(score) = model.score(&plaintext) else {
+                    break;
+                };
+                ranked.push((
+                    score,
+                    Crack {
+                        language: model.name.clone(),
+                        shift,
+                        plaintext,
+                        method: Method::Statistics,
+                        margin: 0.0,
+                    },
+                ));
+            }
+        }
+        ranked.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        ranked
+    }
+
+    /// the most plausible plaintext of `ciphertext`. when it beats the runner-up by less than
+    /// `min_margin` and has none of the keywords, the most plausible plaintext under any shift
+    /// that has one is taken instead. `None` if no language has letters in the ciphertext
+    pub fn crack(&self, ciphertext: &str) -> Option<Crack> {
+        let ranked = self.ranked(ciphertext);
+        let (best, crack) = ranked.first()?;
+        let margin = ranked
+            .get(1)
+            .map_or(f64::INFINITY, |(second, _)| best - second);
+        let has_keyword = |crack: &Crack| {
+            self.keywords
+                .iter()
+                .any(|keyword| crack.plaintext.contains(keyword.as_str()))
+        };
+        if margin < self.min_margin
+            && !has_keyword(crack)
+            && let Some((_, crack)) = ranked.iter().find(|(_, crack)| has_keyword(crack))
+        {
+            return Some(Crack {
+                method: Method::Keyword,
+                margin,
+                ..crack.clone()
+            });
+        }
+        Some(Crack {
+            margin,
+            ..crack.clone()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score() {
+        let english = LanguageModel::english();
+        assert!(english.score("the quick brown fox").unwrap() > english.score("qzx vjk").unwrap());
+        assert_eq!(english.score("δ' 123"), None);
+    }
+
+    #[test]
+    fn test_crack() {
+        let cracker = Cracker::new(LanguageModel::bundled());
+        let plaintexts = [
+            (
+                "English",
+                "Whose woods these are I think I know, his house is in the village though",
+            ),
+            ("Greek", "Ἄνδρα μοι ἔννεπε, Μοῦσα, πολύτροπον"),
+            ("Russian", "Буря мглою небо кроет, вихри снежные крутя"),
+        ];
+        for (language, plaintext) in plaintexts {
+            let model = cracker
+                .models
+                .iter()
+                .find(|model| model.name == language)
+                .unwrap();
+            let ciphertext = model.alphabet.rotate(plaintext, 5);
+            let crack = cracker.crack(&ciphertext).unwrap();
+            assert_eq!(crack.language, language);
+            assert_eq!(crack.shift, model.alphabet.len() - 5);
+            assert_eq!(crack.plaintext, plaintext);
+            assert_eq!(crack.method, Method::Statistics);
+        }
+        assert_eq!(cracker.crack("123"), None);
+    }
+
+    #[test]
+    fn test_keyword_fallback() {
+        let greek = Alphabet::greek();
+        let plaintext = "Ἄνδρα μοι ἔννεπε, Μοῦσα, πολύτροπον";
+        let ciphertext = greek.rotate(plaintext, 7);
+        let cracker = Cracker::new(vec![LanguageModel::greek()]).with_keywords(&["Μοῦσα"]);
+        let crack = cracker.crack(&ciphertext).unwrap();
+        assert_eq!(crack.method, Method::Statistics);
+        assert_eq!(crack.plaintext, plaintext);
+        // a keyword under some less plausible shift doesn't override a confident decryption
+        let keyword = greek.rotate("Μοῦσα", 3);
+        let cracker = Cracker::new(vec![LanguageModel::greek()]).with_keywords(&[&keyword]);
+        let crack = cracker.crack(&ciphertext).unwrap();
+        assert!(crack.margin > DEFAULT_MIN_MARGIN);
+        assert_eq!(crack.method, Method::Statistics);
+        assert_eq!(crack.plaintext, plaintext);
+        // but decides between shifts the statistics can't tell apart
+        let ciphertext = greek.rotate("Ζευς", 7);
+        let statistics = Cracker::new(vec![LanguageModel::greek()]);
+        let crack = statistics.crack(&ciphertext).unwrap();
+        assert!(crack.margin < DEFAULT_MIN_MARGIN);
+        assert_ne!(crack.plaintext, "Ζευς");
+        let crack = statistics
+            .with_keywords(&["Ζευς"])
+            .crack(&ciphertext)
+            .unwrap();
+        assert_eq!(crack.method, Method::Keyword);
+        assert_eq!(crack.shift, 17);
+        assert_eq!(crack.plaintext, "Ζευς");
+    }
+}
@@ -0,0 +1,3 @@
+pub mod alphabet;
+pub mod cipher;
+pub mod crack;
@@ -1,57 +1,59 @@
-use std::fs;
+use day11::alphabet::Alphabet;
+use day11::cipher::Cipher;
+use day11::crack::{Cracker, LanguageModel};
+use std::{env, fs, io};
 
-const UPPERCASE: &[char] = &[
-    'Α', 'Β', 'Γ', 'Δ', 'Ε', 'Ζ', 'Η', 'Θ', 'Ι', 'Κ', 'Λ', 'Μ', 'Ν', 'Ξ', 'Ο', 'Π', 'Ρ', 'Σ', 'Τ',
-    'Υ', 'Φ', 'Χ', 'Ψ', 'Ω',
-];
-const LOWERCASE: &[char] = &[
-    'α', 'β', 'γ', 'δ', 'ε', 'ζ', 'η', 'θ', 'ι', 'κ', 'λ', 'μ', 'ν', 'ξ', 'ο', 'π', 'ρ', 'σ', 'τ',
-    'υ', 'φ', 'χ', 'ψ', 'ω',
-];
-const N_CHARS: usize = 24;
 const ODYSSEUS_VARIANTS: &[&str] = &["Οδυσσευς", "Οδυσσεως", "Οδυσσει", "Οδυσσεα", "Οδυσσευ"];
 
-fn rotate(sentence: &str, shift: usize) -> String {
-    let mut rotated = String::new();
-    let mut chars = sentence
-        .chars()
-        .map(|c| if c == 'ς' { 'σ' } else { c })
-        .peekable();
-    while let Some(c) = chars.next() {
-        if c.is_uppercase() {
-            let n = UPPERCASE.iter().position(|&a| a == c).unwrap();
-            rotated.push(UPPERCASE[(n + shift) % N_CHARS]);
-        } else if c.is_lowercase() {
-            let n = LOWERCASE.iter().position(|&a| a == c).unwrap();
-            let c_shifted = match LOWERCASE[(n + shift) % N_CHARS] {
-                'σ' => match chars.peek() {
-                    Some(next) if !next.is_alphabetic() => 'ς',
-                    _ => 'σ',
-                },
-                c => c,
-            };
-            rotated.push(c_shifted);
-        } else {
-            rotated.push(c);
-        }
-    }
-    rotated
-}
-
 fn contains_odysseus(sentence: &str) -> bool {
     ODYSSEUS_VARIANTS.iter().any(|var| sentence.contains(var))
 }
 
 fn solution(input: &str) -> usize {
+    let cracker = Cracker::new(vec![LanguageModel::greek()]).with_keywords(ODYSSEUS_VARIANTS);
     input
         .lines()
-        .filter_map(|sentence| {
-            (1..N_CHARS).find(|&shift| contains_odysseus(&rotate(sentence, shift)))
-        })
+        .filter_map(|sentence| cracker.crack(sentence))
+        .filter(|crack| contains_odysseus(&crack.plaintext))
+        .map(|crack| crack.shift)
         .sum()
 }
 
+const CIPHER_USAGE: &str = "usage: day11 encrypt|decrypt SCRIPT caesar SHIFT | vigenere KEYWORD | \
+                            affine A B | atbash";
+
+/// `encrypt|decrypt SCRIPT MODE [ARGS]...` applies a cipher over the named alphabet to each line
+/// of stdin
+fn cipher(decrypt: bool, args: &[String]) {
+    let [script, mode, args @ ..] = args else {
+        panic!("{CIPHER_USAGE}");
+    };
+    let alphabet = Alphabet::by_name(script).expect("unknown script");
+    let number = |arg: &String| arg.parse::<usize>().expect(CIPHER_USAGE);
+    let cipher = match (mode.as_str(), args) {
+        ("caesar", [shift]) => Cipher::caesar(alphabet, number(shift)),
+        ("vigenere", [keyword]) => Cipher::vigenere(alphabet, keyword).unwrap(),
+        ("affine", [a, b]) => Cipher::affine(alphabet, number(a), number(b)).unwrap(),
+        ("atbash", []) => Cipher::atbash(alphabet),
+        _ => panic!("{CIPHER_USAGE}"),
+    };
+    for line in io::stdin().lines() {
+        let line = line.unwrap();
+        if decrypt {
+            println!("{}", cipher.decrypt(&line));
+        } else {
+            println!("{}", cipher.encrypt(&line));
+        }
+    }
+}
+
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("encrypt") => return cipher(false, &args[1..]),
+        Some("decrypt") => return cipher(true, &args[1..]),
+        _ => {}
+    }
     let input = fs::read_to_string("input").unwrap();
     let answer = solution(&input);
     println!("{answer}");
@@ -61,18 +63,6 @@ fn main() {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_rotate() {
-        assert_eq!(
-            rotate("σζμ γ' ωοωλδθαξλδμξρ οπξρδυζ οξκτλζσθρ Ξγτρρδτρ.", 1),
-            "την δ' απαμειβομενος προσεφη πολυμητις Οδυσσευς."
-        );
-        assert_eq!(
-            rotate("αφτ κ' λαλψφτ ωπφχλρφτ δξησηρζαλψφτ φελο, Φκβωωλβ.", 18),
-            "τον δ' ετερον σκοπελον χθαμαλωτερον οψει, Οδυσσευ."
-        );
-    }
-
     #[test]
     fn example() {
         let input = fs::read_to_string("test-input").unwrap();
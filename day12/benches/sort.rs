@@ -0,0 +1,150 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use day12::collate::Collator;
+use day12::names::{Convention, Name};
+use std::hint::black_box;
+
+const SURNAMES: &[&str] = &[
+    "Aalto",
+    "Åberg",
+    "Özaydın",
+    "van den Heyden",
+    "Ämtler",
+    "Olofsson",
+    "van Leeuwen",
+    "Vandersteen",
+    "Østergård",
+    "van Leeuw",
+    "Navarrete Ortiz",
+    "Zondervan",
+    "Æbelø",
+    "O'Neill",
+    "Ñíguez Peña",
+    "de Vries",
+    "Müller",
+    "Łukasiewicz",
+    "Dvořák",
+    "MacNeill",
+];
+const GIVEN: &[&str] = &[
+    "Alvar",
+    "Rosa-Maria",
+    "Zeynep",
+    "Harm",
+    "Lorena",
+    "Mikael",
+    "Joke",
+    "Willy",
+    "Magnus",
+    "Floor",
+    "Dolores",
+    "Jan Peter",
+    "Aurora",
+    "Cara",
+    "María de los Ángeles",
+    "Émile",
+];
+
+/// deterministic pseudo-random `Surname, Given` names, with a number appended to the surname so
+/// that most are distinct
+fn names(count: usize) -> Vec<String> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        usize::try_from(state >> 32).unwrap()
+    };
+    (0..count)
+        .map(|_| {
+            let surname = SURNAMES[next() % SURNAMES.len()];
+            let given = GIVEN[next() % GIVEN.len()];
+            format!("{surname}{}, {given}", next() % 1000)
+        })
+        .collect()
+}
+
+/// day12's sort before it had a collator: a transliterated, lowercased `String` key, which
+/// `sort_by_key` recomputes for every comparison
+fn sort_english(mut lines: Vec<String>) -> Vec<String> {
+    lines.sort_by_key(|line| {
+        line.chars()
+            .filter(|c| c.is_alphabetic() || *c == ',')
+            .map(|c| deunicode::deunicode_char(c).unwrap().to_lowercase())
+            .collect::<String>()
+    });
+    lines
+}
+
+/// the whole sort, keys included: the old `sort_english` against sorting by collation keys and
+/// by byte keys
+fn sort(c: &mut Criterion) {
+    let collator = Collator::ducet();
+    let convention = Convention::AsWritten;
+    let mut group = c.benchmark_group("sort");
+    group.sample_size(10);
+    for count in [10_000, 100_000, 1_000_000] {
+        let names = names(count);
+        group.bench_with_input(
+            BenchmarkId::new("sort_english", count),
+            &names,
+            |b, names| b.iter(|| black_box(sort_english(names.clone()))),
+        );
+        group.bench_with_input(BenchmarkId::new("sort_key", count), &names, |b, names| {
+            b.iter(|| {
+                let mut names = names.clone();
+                names.sort_by_cached_key(|name| {
+                    convention.sort_key(&Name::parse(name).unwrap(), &collator)
+                });
+                black_box(names)
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("byte_key", count), &names, |b, names| {
+            b.iter(|| {
+                let mut names = names.clone();
+                names.sort_by_cached_key(|name| {
+                    convention.byte_key(&Name::parse(name).unwrap(), &collator)
+                });
+                black_box(names)
+            });
+        });
+    }
+    group.finish();
+}
+
+/// sorting keys that were computed beforehand, as an index would store them
+fn compare(c: &mut Criterion) {
+    let collator = Collator::ducet();
+    let convention = Convention::AsWritten;
+    let names: Vec<Name> = names(1_000_000)
+        .iter()
+        .map(|name| Name::parse(name).unwrap())
+        .collect();
+    let mut group = c.benchmark_group("compare");
+    group.sample_size(10);
+    let keys: Vec<_> = names
+        .iter()
+        .map(|name| convention.sort_key(name, &collator))
+        .collect();
+    group.bench_function("sort_key", |b| {
+        b.iter(|| {
+            let mut keys = keys.clone();
+            keys.sort_unstable();
+            black_box(keys)
+        });
+    });
+    let keys: Vec<_> = names
+        .iter()
+        .map(|name| convention.byte_key(name, &collator))
+        .collect();
+    group.bench_function("byte_key", |b| {
+        b.iter(|| {
+            let mut keys = keys.clone();
+            keys.sort_unstable();
+            black_box(keys)
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, sort, compare);
+criterion_main!(benches);
@@ -0,0 +1,464 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::Path;
+use std::{fmt, fs, io};
+use unicode_normalization::char::{canonical_combining_class, decompose_canonical};
+
+/// DUCET weights are stored shifted left by this much, leaving room for tailored weights between
+/// neighbouring ones
+pub const WEIGHT_SHIFT: u32 = 16;
+
+/// one collation element: a weight for each of the first three levels
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Element {
+    pub primary: u32,
+    pub secondary: u32,
+    pub tertiary: u32,
+    /// spaces, punctuation and symbols, which [`Alternate::Shifted`] moves to the fourth level
+    pub variable: bool,
+}
+
+impl Element {
+    /// an element with the given DUCET weights
+    pub fn new(primary: u16, secondary: u16, tertiary: u16, variable: bool) -> Self {
+        Self {
+            primary: u32::from(primary) << WEIGHT_SHIFT,
+            secondary: u32::from(secondary) << WEIGHT_SHIFT,
+            tertiary: u32::from(tertiary) << WEIGHT_SHIFT,
+            variable,
+        }
+    }
+
+    pub(crate) fn is_ignorable(&self) -> bool {
+        self.primary == 0 && self.secondary == 0 && self.tertiary == 0
+    }
+}
+
+/// how many levels of difference are significant: base letters, then accents, then case, then
+/// (with [`Alternate::Shifted`]) punctuation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Strength {
+    Primary,
+    Secondary,
+    Tertiary,
+    Quaternary,
+}
+
+/// how variable elements are weighted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alternate {
+    /// variable elements are compared like letters
+    NonIgnorable,
+    /// variable elements are ignored up to the quaternary level, so "de luca" sorts with "deluca"
+    Shifted,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    /// the line of the table that couldn't be parsed
+    Syntax(usize),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Syntax(line) => write!(f, "invalid collation table entry on line {line}"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// unified ideographs weighted from 0xFB40, as UTS #10 section 10.1.3 puts it
+const CORE_HAN: &[(u32, u32)] = &[
+    (0x4E00, 0x9FFF),
+    (0xFA0E, 0xFA0F),
+    (0xFA11, 0xFA11),
+    (0xFA13, 0xFA14),
+    (0xFA1F, 0xFA1F),
+    (0xFA21, 0xFA21),
+    (0xFA23, 0xFA24),
+    (0xFA27, 0xFA29),
+];
+
+/// the extension blocks of unified ideographs, weighted from 0xFB80
+const OTHER_HAN: &[(u32, u32)] = &[
+    (0x3400, 0x4DBF),
+    (0x20000, 0x2A6DF),
+    (0x2A700, 0x2EBEF),
+    (0x30000, 0x3134F),
+];
+
+fn in_ranges(ranges: &[(u32, u32)], code_point: u32) -> bool {
+    ranges
+        .iter()
+        .any(|&(start, end)| (start..=end).contains(&code_point))
+}
+
+fn parse_hex<T: TryFrom<u32>>(hex: &str) -> Option<T> {
+    u32::from_str_radix(hex.trim(), 16).ok()?.try_into().ok()
+}
+
+/// `[.1FA2.0020.0002][*0209.0020.0002]...`
+fn parse_elements(text: &str) -> Option<Vec<Element>> {
+    text.split('[')
+        .skip(1)
+        .map(|element| {
+            let element = element.trim_end().strip_suffix(']')?;
+            let variable = match element.chars().next()? {
+                '*' => true,
+                '.' => false,
+                _ => return None,
+            };
+            let mut weights = element[1..].split('.').map(parse_hex);
+            let (Some(primary), Some(secondary), Some(tertiary), None) = (
+                weights.next()?,
+                weights.next()?,
+                weights.next()?,
+                weights.next(),
+            ) else {
+                return None;
+            };
+            Some(Element::new(primary, secondary, tertiary, variable))
+        })
+        .collect()
+}
+
+/// compares strings by the Unicode Collation Algorithm, using a table in the format of the
+/// DUCET's allkeys.txt
+#[derive(Debug, Clone)]
+pub struct Collator {
+    /// single characters and contractions, each mapped to one or more (expansions) elements
+    pub(crate) entries: HashMap<Vec<char>, Vec<Element>>,
+    /// the length of the longest contraction
+    max_len: usize,
+    /// `(first, last, base)` ranges given their own implicit primary weights
+    implicit: Vec<(u32, u32, u16)>,
+    pub strength: Strength,
+    pub alternate: Alternate,
+}
+
+impl Collator {
+    pub fn from_allkeys(table: &str) -> Result<Self, LoadError> {
+        let mut collator = Self {
+            entries: HashMap::new(),
+            max_len: 1,
+            implicit: Vec::new(),
+            strength: Strength::Tertiary,
+            alternate: Alternate::Shifted,
+        };
+        for (i, line) in table.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            let syntax = || LoadError::Syntax(i + 1);
+            if let Some(ranges) = line.strip_prefix("@implicitweights") {
+                let (range, base) = ranges.split_once(';').ok_or_else(syntax)?;
+                let (first, last) = range.split_once("..").ok_or_else(syntax)?;
+                collator.implicit.push((
+                    parse_hex(first).ok_or_else(syntax)?,
+                    parse_hex(last).ok_or_else(syntax)?,
+                    parse_hex(base).ok_or_else(syntax)?,
+                ));
+                continue;
+            }
+            if line.is_empty() || line.starts_with('@') {
+                continue;
+            }
+            let (chars, elements) = line.split_once(';').ok_or_else(syntax)?;
+            let chars: Vec<char> = chars
+                .split_whitespace()
+                .map(|hex| parse_hex::<u32>(hex).and_then(char::from_u32))
+                .collect::<Option<_>>()
+                .ok_or_else(syntax)?;
+            let elements = parse_elements(elements).ok_or_else(syntax)?;
+            collator.insert(chars, elements);
+        }
+        Ok(collator)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        Self::from_allkeys(&fs::read_to_string(path)?)
+    }
+
+    /// the Default Unicode Collation Element Table, version 13, bundled in `data/allkeys.txt`
+    pub fn ducet() -> Self {
+        Self::from_allkeys(include_str!("../data/allkeys.txt")).unwrap()
+    }
+
+    pub fn with_strength(mut self, strength: Strength) -> Self {
+        self.strength = strength;
+        self
+    }
+
+    pub fn with_alternate(mut self, alternate: Alternate) -> Self {
+        self.alternate = alternate;
+        self
+    }
+
+    /// maps `chars`, one character or a contraction, to `elements`, replacing any previous entry
+    pub fn insert(&mut self, chars: Vec<char>, elements: Vec<Element>) {
+        self.max_len = self.max_len.max(chars.len());
+        self.entries.insert(chars, elements);
+    }
+
+    /// the elements `chars` maps to, if it is in the table
+    pub fn get(&self, chars: &[char]) -> Option<&[Element]> {
+        self.entries.get(chars).map(Vec::as_slice)
+    }
+
+    /// the elements of a character that isn't in the table, derived from its code point
+    fn implicit(&self, c: char) -> [Element; 2] {
+        let code_point = c as u32;
+        let (first, second) = match self
+            .implicit
+            .iter()
+            .find(|&&(first, last, _)| (first..=last).contains(&code_point))
+        {
+            Some(&(first, _, base)) => (base, code_point - first),
+            None => {
+                let base = if in_ranges(CORE_HAN, code_point) {
+                    0xFB40
+                } else if in_ranges(OTHER_HAN, code_point) {
+                    0xFB80
+                } else {
+                    0xFBC0
+                };
+                (base + (code_point >> 15) as u16, code_point & 0x7FFF)
+            }
+        };
+        [
+            Element::new(first, 0x0020, 0x0002, false),
+            Element::new(second as u16 | 0x8000, 0, 0, false),
+        ]
+    }
+
+    /// the collation elements of `text`, after canonical decomposition, matching the longest
+    /// contraction at each point. a contraction may also take combining marks that aren't
+    /// adjacent to it, so long as they aren't blocked by the marks in between
+    pub fn elements(&self, text: &str) -> Vec<Element> {
+        self.elements_with_ends(text)
+            .into_iter()
+            .map(|(element, _)| element)
+            .collect()
+    }
+
+    /// [`Collator::elements`], each with the byte offset in `text` just past the characters it
+    /// and the elements before it came from. the elements of a character or contraction share
+    /// one end
+    pub(crate) fn elements_with_ends(&self, text: &str) -> Vec<(Element, usize)> {
+        let mut chars = Vec::new();
+        let mut ends = Vec::new();
+        for (i, c) in text.char_indices() {
+            decompose_canonical(c, |d| {
+                chars.push(d);
+                ends.push(i + c.len_utf8());
+            });
+        }
+        // the canonical ordering of combining marks, which decomposing one character at a time
+        // misses across characters
+        for j in 1..chars.len() {
+            let mut k = j;
+            while k > 0 {
+                let class = canonical_combining_class(chars[k]);
+                if class == 0 || canonical_combining_class(chars[k - 1]) <= class {
+                    break;
+                }
+                chars.swap(k - 1, k);
+                ends.swap(k - 1, k);
+                k -= 1;
+            }
+        }
+        let mut elements = Vec::new();
+        let mut end = 0;
+        let mut i = 0;
+        while i < chars.len() {
+            let longest = (1..=self.max_len.min(chars.len() - i))
+                .rev()
+                .find(|&len| self.entries.contains_key(&chars[i..i + len]));
+            let Some(len) = longest else {
+                end = end.max(ends[i]);
+                elements.extend(self.implicit(chars[i]).map(|element| (element, end)));
+                i += 1;
+                continue;
+            };
+            let mut matched = chars[i..i + len].to_vec();
+            end = ends[i..i + len].iter().fold(end, |end, &e| end.max(e));
+            let mut blocking = 0;
+            let mut j = i + len;
+            while j < chars.len() {
+                let class = canonical_combining_class(chars[j]);
+                if class == 0 {
+                    break;
+                }
+                if blocking < class {
+                    matched.push(chars[j]);
+                    if self.entries.contains_key(&matched) {
+                        chars.remove(j);
+                        end = end.max(ends.remove(j));
+                        continue;
+                    }
+                    matched.pop();
+                }
+                blocking = class;
+                j += 1;
+            }
+            elements.extend(self.entries[&matched].iter().map(|&element| (element, end)));
+            i += len;
+        }
+        elements
+    }
+
+    /// the weights of each element at all four levels, after variable weighting, with the end of
+    /// the text it came from as in [`Collator::elements_with_ends`]
+    pub(crate) fn weights(&self, text: &str) -> Vec<([u32; 4], usize)> {
+        let mut after_variable = false;
+        self.elements_with_ends(text)
+            .into_iter()
+            .map(|(element, end)| {
+                let Element {
+                    primary,
+                    secondary,
+                    tertiary,
+                    variable,
+                } = element;
+                let weights = if self.alternate == Alternate::NonIgnorable || element.is_ignorable()
+                {
+                    let quaternary = if element.is_ignorable() { 0 } else { u32::MAX };
+                    [primary, secondary, tertiary, quaternary]
+                } else if variable {
+                    after_variable = true;
+                    [0, 0, 0, primary]
+                } else if primary == 0 && after_variable {
+                    [0; 4]
+                } else {
+                    after_variable &= primary == 0;
+                    [primary, secondary, tertiary, u32::MAX]
+                };
+                (weights, end)
+            })
+            .collect()
+    }
+
+    /// the non-zero weights of each significant level in turn, separated by zeros, so that keys
+    /// compare as their strings collate
+    pub fn sort_key(&self, text: &str) -> Vec<u32> {
+        self.sort_key_at(text, self.strength)
+    }
+
+    /// [`Collator::sort_key`] at `strength` rather than the collator's own
+    pub fn sort_key_at(&self, text: &str, strength: Strength) -> Vec<u32> {
+        key_of(&self.weights(text), strength)
+    }
+
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        self.sort_key(a).cmp(&self.sort_key(b))
+    }
+}
+
+/// the sort key at `strength` of text with these [`Collator::weights`]
+pub(crate) fn key_of(weights: &[([u32; 4], usize)], strength: Strength) -> Vec<u32> {
+    let mut key = Vec::new();
+    for level in 0..=strength as usize {
+        if level > 0 {
+            key.push(0);
+        }
+        key.extend(weights.iter().map(|(w, _)| w[level]).filter(|&w| w != 0));
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted<'a>(collator: &Collator, words: &[&'a str]) -> Vec<&'a str> {
+        let mut words = words.to_vec();
+        words.sort_by(|a, b| collator.compare(a, b));
+        words
+    }
+
+    #[test]
+    fn test_parse() {
+        let collator = Collator::from_allkeys(
+            "@version 13.0.0\n\
+             0061  ; [.1FA2.0020.0002] # a\n\
+             006C 00B7 ; [.20D6.0020.0002][.0000.0118.0002] # l·\n",
+        )
+        .unwrap();
+        assert_eq!(
+            collator.get(&['a']).unwrap()[0].primary,
+            0x1FA2 << WEIGHT_SHIFT
+        );
+        assert_eq!(collator.get(&['l', '·']).unwrap().len(), 2);
+        assert!(matches!(
+            Collator::from_allkeys("0061 ; [.1FA2.0020]"),
+            Err(LoadError::Syntax(1))
+        ));
+    }
+
+    #[test]
+    fn test_levels() {
+        let collator = Collator::ducet();
+        let words = ["Á", "b", "á", "A", "a"];
+        assert_eq!(sorted(&collator, &words), ["a", "A", "á", "Á", "b"]);
+        let collator = collator.with_strength(Strength::Primary);
+        assert_eq!(collator.compare("Á", "a"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_variable_weighting() {
+        let shifted = Collator::ducet();
+        assert_eq!(
+            sorted(&shifted, &["de luca", "delta"]),
+            ["delta", "de luca"]
+        );
+        assert_eq!(
+            shifted
+                .with_strength(Strength::Tertiary)
+                .compare("deluca", "de luca"),
+            Ordering::Equal
+        );
+        let shifted = Collator::ducet().with_strength(Strength::Quaternary);
+        assert_eq!(shifted.compare("deluca", "de luca"), Ordering::Greater);
+        let non_ignorable = Collator::ducet().with_alternate(Alternate::NonIgnorable);
+        assert_eq!(
+            sorted(&non_ignorable, &["delta", "de luca"]),
+            ["de luca", "delta"]
+        );
+    }
+
+    #[test]
+    fn test_contractions_and_expansions() {
+        let collator = Collator::ducet();
+        let short_i = collator.elements("й");
+        assert_eq!(short_i.len(), 1);
+        assert_ne!(short_i[0].primary, collator.elements("и")[0].primary);
+        // the breve reaches past the dot below to form й
+        assert_eq!(collator.elements("и\u{323}\u{306}")[0], short_i[0]);
+        assert_eq!(collator.elements("æ").len(), 3);
+        assert_eq!(sorted(&collator, &["af", "æ", "ae"]), ["ae", "æ", "af"]);
+    }
+
+    #[test]
+    fn test_implicit_weights() {
+        let collator = Collator::ducet();
+        let han = collator.elements("\u{4E00}");
+        let primaries = |elements: &[Element]| {
+            (
+                elements[0].primary >> WEIGHT_SHIFT,
+                elements[1].primary >> WEIGHT_SHIFT,
+            )
+        };
+        assert_eq!(primaries(&han), (0xFB40, 0xCE00));
+        let tangut = collator.elements("\u{17001}");
+        assert_eq!(primaries(&tangut), (0xFB00, 0x8001));
+        assert_eq!(collator.compare("\u{4E00}", "\u{3400}"), Ordering::Less);
+    }
+}
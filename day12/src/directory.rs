@@ -0,0 +1,418 @@
+use crate::collate::{Collator, Strength};
+use crate::names::{Convention, Name};
+use crate::phone::{Metadata, PhoneError, PhoneNumber, SEPARATORS};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryError {
+    /// no `:` between the name and the number
+    MissingNumber,
+    /// no `,` between the family name and the given names
+    MissingGivenNames,
+    EmptyFamilyName,
+    EmptyGivenNames,
+    /// the number has something other than digits, `+` and the [`SEPARATORS`] of phone numbers,
+    /// or no digits at all
+    InvalidNumber(String),
+}
+
+impl fmt::Display for EntryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingNumber => f.write_str("expected ': ' before the phone number"),
+            Self::MissingGivenNames => f.write_str("expected ', ' before the given names"),
+            Self::EmptyFamilyName => f.write_str("family name is empty"),
+            Self::EmptyGivenNames => f.write_str("given names are empty"),
+            Self::InvalidNumber(number) => write!(f, "invalid phone number {number:?}"),
+        }
+    }
+}
+
+impl std::error::Error for EntryError {}
+
+/// one `Surname, Given: number` line of a phone directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryEntry {
+    pub name: Name,
+    pub phone: String,
+}
+
+impl FromStr for DirectoryEntry {
+    type Err = EntryError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let (name, phone) = line.rsplit_once(':').ok_or(EntryError::MissingNumber)?;
+        let (family, given) = name.split_once(',').ok_or(EntryError::MissingGivenNames)?;
+        if given.trim().is_empty() {
+            return Err(EntryError::EmptyGivenNames);
+        }
+        let name = match family.trim() {
+            "" => None,
+            _ => Name::parse(name),
+        }
+        .ok_or(EntryError::EmptyFamilyName)?;
+        let phone = phone.trim();
+        let valid = |c: char| c.is_ascii_digit() || c == '+' || SEPARATORS.contains(&c);
+        if !phone.chars().all(valid) || !phone.chars().any(|c| c.is_ascii_digit()) {
+            return Err(EntryError::InvalidNumber(phone.to_owned()));
+        }
+        Ok(Self {
+            name,
+            phone: phone.to_owned(),
+        })
+    }
+}
+
+impl DirectoryEntry {
+    /// the entry as `convention` lists it, e.g. `Heyden, Harm van den: 0168131` in Dutch
+    pub fn line(&self, convention: Convention) -> String {
+        format!("{}: {}", convention.format(&self.name), self.phone)
+    }
+
+    /// the phone number, read as a national number of `region` unless it's international
+    pub fn phone_number(
+        &self,
+        metadata: &Metadata,
+        region: &str,
+    ) -> Result<PhoneNumber, PhoneError> {
+        metadata.parse_number(&self.phone, region)
+    }
+
+    /// the phone number as a directory for `region` shows it: in national format if it's a
+    /// number of `region`, in international format if not, and as written if it doesn't parse
+    pub fn display_phone(&self, metadata: &Metadata, region: &str) -> String {
+        let home = metadata.region(region).map(|country| country.calling_code);
+        match self.phone_number(metadata, region) {
+            Ok(number) if home == Some(number.calling_code) => number.national(metadata),
+            Ok(number) => number.international(metadata),
+            Err(_) => self.phone.clone(),
+        }
+    }
+}
+
+/// sorts `entries` by `convention` and `collator`
+pub fn sort(entries: &mut [DirectoryEntry], collator: &Collator, convention: Convention) {
+    entries.sort_by_cached_key(|entry| convention.byte_key(&entry.name, collator));
+}
+
+/// the first primary weight of `family`, which decides its section, and the section's heading:
+/// the plain capital from A to Z with that weight if there is one, otherwise the shortest start
+/// of `family` with it alone. so under the DUCET "Åberg" and "Æbelø" are under A, while in
+/// Swedish, where Å is a letter of its own, "Åberg" is under Å, and in Czech "Chata" is under Ch
+fn section(family: &str, collator: &Collator) -> Option<(u32, String)> {
+    let primary = |text: &str| collator.sort_key_at(text, Strength::Primary);
+    let weight = *primary(family).first()?;
+    let heading = ('A'..='Z')
+        .map(String::from)
+        .find(|letter| primary(letter) == [weight])
+        .or_else(|| {
+            let ends = family.char_indices().map(|(i, c)| i + c.len_utf8());
+            let letter = ends
+                .map(|end| &family[..end])
+                .find(|start| primary(start) == [weight])?;
+            let mut chars = letter.chars();
+            let first = chars.next()?;
+            Some(
+                first
+                    .to_uppercase()
+                    .chain(chars.flat_map(char::to_lowercase))
+                    .collect(),
+            )
+        })
+        .unwrap_or_default();
+    Some((weight, heading))
+}
+
+/// `entries`, which are sorted, grouped under their section headings. where the entries of a
+/// section start with different forms of its letter, the heading is the one sorting first, so
+/// Swedish "Æbelø" and "Ärlig" are both under Ä
+fn sections<'a>(
+    entries: &'a [DirectoryEntry],
+    collator: &Collator,
+    convention: Convention,
+) -> Vec<(String, Vec<&'a DirectoryEntry>)> {
+    let mut sections: Vec<(u32, String, Vec<&DirectoryEntry>)> = Vec::new();
+    for entry in entries {
+        let fields = convention.sort_fields(&entry.name);
+        let (key, heading) = section(&fields[0], collator).unwrap_or_default();
+        match sections.last_mut() {
+            Some((last, first, group)) if *last == key => {
+                if collator.compare(&heading, first).is_lt() {
+                    *first = heading;
+                }
+                group.push(entry);
+            }
+            _ => sections.push((key, heading, vec![entry])),
+        }
+    }
+    sections
+        .into_iter()
+        .map(|(_, heading, group)| (heading, group))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// a heading line before each section, and a blank line between sections
+    Text,
+    /// `section,family_name,infix,given_names,phone` with a header row, the phone number in
+    /// E.164 format if it parses
+    Csv,
+    /// an `<h2>` and a `<ul>` for each section, each phone number that parses a `tel:` link
+    Html,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "text" => Ok(Self::Text),
+            "csv" => Ok(Self::Csv),
+            "html" => Ok(Self::Html),
+            _ => Err(format!("unknown format {name:?}")),
+        }
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `entries` sorted and listed in sections as `format`, their phone numbers read as numbers of
+/// `region`. with no region they're listed as written
+pub fn render(
+    entries: &[DirectoryEntry],
+    collator: &Collator,
+    convention: Convention,
+    format: Format,
+    metadata: &Metadata,
+    region: Option<&str>,
+) -> String {
+    let number = |entry: &DirectoryEntry| {
+        region.and_then(|region| entry.phone_number(metadata, region).ok())
+    };
+    let shown = |entry: &DirectoryEntry| match region {
+        Some(region) => entry.display_phone(metadata, region),
+        None => entry.phone.clone(),
+    };
+    let mut entries = entries.to_vec();
+    sort(&mut entries, collator, convention);
+    let sections = sections(&entries, collator, convention);
+    let mut output = String::new();
+    match format {
+        Format::Text => {
+            for (i, (heading, group)) in sections.iter().enumerate() {
+                if i > 0 {
+                    output.push('\n');
+                }
+                output.push_str(heading);
+                output.push('\n');
+                for entry in group {
+                    output.push_str(&format!(
+                        "{}: {}\n",
+                        convention.format(&entry.name),
+                        shown(entry)
+                    ));
+                }
+            }
+        }
+        Format::Csv => {
+            output.push_str("section,family_name,infix,given_names,phone\r\n");
+            for (heading, group) in &sections {
+                for entry in group {
+                    let name = &entry.name;
+                    let phone = match number(entry) {
+                        Some(number) => number.e164(),
+                        None => entry.phone.clone(),
+                    };
+                    let fields = [heading, &name.surname, &name.infix, &name.given, &phone];
+                    let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+                    output.push_str(&fields.join(","));
+                    output.push_str("\r\n");
+                }
+            }
+        }
+        Format::Html => {
+            for (heading, group) in &sections {
+                output.push_str(&format!("<h2>{}</h2>\n<ul>\n", html_escape(heading)));
+                for entry in group {
+                    let shown = html_escape(&shown(entry));
+                    let phone = match number(entry) {
+                        Some(number) => {
+                            format!("<a href=\"{}\">{shown}</a>", number.rfc3966(metadata))
+                        }
+                        None => shown,
+                    };
+                    output.push_str(&format!(
+                        "<li>{}: {phone}</li>\n",
+                        html_escape(&convention.format(&entry.name)),
+                    ));
+                }
+                output.push_str("</ul>\n");
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(lines: &[&str]) -> Vec<DirectoryEntry> {
+        lines.iter().map(|line| line.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let entry: DirectoryEntry = "van den Heyden, Harm: 0168131".parse().unwrap();
+        assert_eq!(entry.name.surname, "Heyden");
+        assert_eq!(entry.name.infix, "van den");
+        assert_eq!(entry.name.given, "Harm");
+        assert_eq!(entry.phone, "0168131");
+        assert_eq!(
+            entry.line(Convention::Dutch),
+            "Heyden, Harm van den: 0168131"
+        );
+        let entry: DirectoryEntry = "Brecht, Bertolt: 030/1234567".parse().unwrap();
+        assert_eq!(entry.phone, "030/1234567");
+        let entry: DirectoryEntry = "Curie, Marie: 01.23.45.67.89".parse().unwrap();
+        assert_eq!(entry.phone, "01.23.45.67.89");
+        let errors = [
+            ("Aalto, Alvar 0192872", EntryError::MissingNumber),
+            ("Aalto Alvar: 0192872", EntryError::MissingGivenNames),
+            (" , Alvar: 0192872", EntryError::EmptyFamilyName),
+            ("Aalto, : 0192872", EntryError::EmptyGivenNames),
+            (
+                "Aalto, Alvar: 019x872",
+                EntryError::InvalidNumber("019x872".to_owned()),
+            ),
+            ("Aalto, Alvar: ", EntryError::InvalidNumber(String::new())),
+        ];
+        for (line, error) in errors {
+            assert_eq!(line.parse::<DirectoryEntry>(), Err(error), "{line}");
+        }
+    }
+
+    #[test]
+    fn test_sections() {
+        let entries = entries(&[
+            "Åberg, Rosa-Maria: 0110966",
+            "Aalto, Alvar: 0192872",
+            "Östergård, Magnus: 0113959",
+            "Ärlig, Lorena: 0112717",
+            "Olofsson, Mikael: 0103652",
+            "Æbelø, Aurora: 0113267",
+        ]);
+        let metadata = Metadata::bundled();
+        let render = |collator: &Collator| {
+            render(
+                &entries,
+                collator,
+                Convention::AsWritten,
+                Format::Text,
+                &metadata,
+                None,
+            )
+        };
+        assert_eq!(
+            render(&Collator::ducet()),
+            "A\nAalto, Alvar: 0192872\nÅberg, Rosa-Maria: 0110966\nÆbelø, Aurora: 0113267\n\
+             Ärlig, Lorena: 0112717\n\n\
+             O\nOlofsson, Mikael: 0103652\nÖstergård, Magnus: 0113959\n"
+        );
+        assert_eq!(
+            render(&Collator::for_locale("sv").unwrap()),
+            "A\nAalto, Alvar: 0192872\n\nO\nOlofsson, Mikael: 0103652\n\n\
+             Å\nÅberg, Rosa-Maria: 0110966\n\nÄ\nÆbelø, Aurora: 0113267\nÄrlig, Lorena: 0112717\n\n\
+             Ö\nÖstergård, Magnus: 0113959\n"
+        );
+    }
+
+    #[test]
+    fn test_formats() {
+        let entries = entries(&[
+            "van den Heyden, Harm: 0168131",
+            "O'Neill, Cara <C>: 0109551",
+        ]);
+        let metadata = Metadata::bundled();
+        let ducet = Collator::ducet();
+        let render = |entries: &[DirectoryEntry], format, region| {
+            render(
+                entries,
+                &ducet,
+                Convention::Dutch,
+                format,
+                &metadata,
+                region,
+            )
+        };
+        assert_eq!(
+            render(&entries, Format::Csv, None),
+            "section,family_name,infix,given_names,phone\r\n\
+             H,Heyden,van den,Harm,0168131\r\n\
+             O,O'Neill,,Cara <C>,0109551\r\n"
+        );
+        assert_eq!(
+            render(&entries, Format::Html, None),
+            "<h2>H</h2>\n<ul>\n\
+             <li>Heyden, Harm van den: 0168131</li>\n</ul>\n\
+             <h2>O</h2>\n<ul>\n\
+             <li>O'Neill, Cara &lt;C&gt;: 0109551</li>\n</ul>\n"
+        );
+        let swedish = self::entries(&[
+            "Olofsson, Mikael: 070-123 45 67",
+            "Smith, Jo: +1 (212) 555-0123",
+            "Åberg, Rosa: 12",
+        ]);
+        assert_eq!(
+            render(&swedish, Format::Text, Some("SE")),
+            "A\nÅberg, Rosa: 12\n\n\
+             O\nOlofsson, Mikael: 070 123 45 67\n\n\
+             S\nSmith, Jo: +1 212 555 0123\n"
+        );
+        assert_eq!(
+            render(&swedish, Format::Csv, Some("SE")),
+            "section,family_name,infix,given_names,phone\r\n\
+             A,Åberg,,Rosa,12\r\n\
+             O,Olofsson,,Mikael,+46701234567\r\n\
+             S,Smith,,Jo,+12125550123\r\n"
+        );
+        assert_eq!(
+            render(&swedish, Format::Html, Some("SE")),
+            "<h2>A</h2>\n<ul>\n<li>Åberg, Rosa: 12</li>\n</ul>\n\
+             <h2>O</h2>\n<ul>\n\
+             <li>Olofsson, Mikael: <a href=\"tel:+46-70-123-45-67\">070 123 45 67</a></li>\n</ul>\n\
+             <h2>S</h2>\n<ul>\n\
+             <li>Smith, Jo: <a href=\"tel:+1-212-555-0123\">+1 212 555 0123</a></li>\n</ul>\n"
+        );
+        assert_eq!(
+            csv_field("Navarrete, \"Lola\""),
+            "\"Navarrete, \"\"Lola\"\"\""
+        );
+        assert_eq!(
+            "pdf".parse::<Format>(),
+            Err("unknown format \"pdf\"".to_owned())
+        );
+    }
+}
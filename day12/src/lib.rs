@@ -0,0 +1,7 @@
+pub mod collate;
+pub mod directory;
+pub mod names;
+pub mod phone;
+pub mod search;
+pub mod sortkey;
+pub mod tailoring;
@@ -1,75 +1,107 @@
-use std::{collections::HashMap, fs};
-use unicode_normalization::UnicodeNormalization;
+use day12::collate::Collator;
+use day12::directory::{self, DirectoryEntry, Format};
+use day12::names::Convention;
+use day12::phone::Metadata;
+use day12::tailoring::TailoringError;
+use std::{env, fs};
 
-fn sort_english(mut lines: Vec<String>) -> Vec<String> {
-    lines.sort_by_key(|line| {
-        line.chars()
-            .filter(|c| c.is_alphabetic() || *c == ',')
-            .map(|c| deunicode::deunicode_char(c).unwrap().to_lowercase())
-            .collect::<String>()
-    });
-    lines
-}
-
-fn sort_swedish(mut lines: Vec<String>) -> Vec<String> {
-    let char_to_int: HashMap<char, u8> = ",abcdefghijklmnopqrstuvwxyzåäö"
-        .chars()
-        .enumerate()
-        .map(|(i, c)| (c, i as u8))
-        .collect();
-    lines.sort_by_key(|line| {
-        line.to_lowercase()
-            .chars()
-            .map(|c| match c {
-                'æ' => 'ä',
-                'ø' => 'ö',
-                c if char_to_int.contains_key(&c) => c,
-                c => c.nfd().next().unwrap(),
-            })
-            .filter_map(|c| char_to_int.get(&c).copied())
-            .collect::<Vec<u8>>()
-    });
-    lines
-}
+const DIRECTORY_USAGE: &str =
+    "usage: day12 directory [--tailorings DIR] [--region CC] LOCALE [text|csv|html]";
 
-fn move_dutch_infix(line: &str) -> String {
-    let surname_start = line.chars().position(char::is_uppercase).unwrap();
-    if surname_start == 0 {
-        line.to_owned()
-    } else {
-        let name_end = line.find(':').unwrap();
-        format!(
-            "{} {}{}",
-            &line[surname_start..name_end],
-            &line[0..surname_start - 1],
-            &line[name_end..]
-        )
-    }
+fn parse(input: &str) -> Vec<DirectoryEntry> {
+    input.lines().map(|line| line.parse().unwrap()).collect()
 }
 
-fn sort_dutch(mut lines: Vec<String>) -> Vec<String> {
-    for line in &mut lines {
-        *line = move_dutch_infix(line);
-    }
-    sort_english(lines)
+fn sort_names(
+    collator: &Collator,
+    convention: Convention,
+    mut entries: Vec<DirectoryEntry>,
+) -> Vec<DirectoryEntry> {
+    directory::sort(&mut entries, collator, convention);
+    entries
 }
 
-fn middle_phone_number(lines: &[String]) -> u64 {
-    let line = &lines[lines.len() / 2];
-    let phone_number = line.split_whitespace().last().unwrap();
-    phone_number.parse().unwrap()
+/// the digits of the middle entry's number. the puzzle's numbers belong to no real country, so
+/// they're taken as written
+fn middle_phone_number(entries: &[DirectoryEntry]) -> u64 {
+    let phone = &entries[entries.len() / 2].phone;
+    let digits: String = phone.chars().filter(char::is_ascii_digit).collect();
+    digits.parse().unwrap()
 }
 
 fn solution(input: &str) -> u64 {
-    let lines: Vec<String> = input.lines().map(ToOwned::to_owned).collect();
-    let english = sort_english(lines.clone());
-    let swedish = sort_swedish(lines.clone());
-    let dutch = sort_dutch(lines);
+    let entries = parse(input);
+    let collator = Collator::ducet();
+    let english = sort_names(&collator, Convention::AsWritten, entries.clone());
+    let swedish = sort_names(
+        &Collator::for_locale("sv").unwrap(),
+        Convention::for_locale("sv"),
+        entries.clone(),
+    );
+    let dutch = sort_names(&collator, Convention::Dutch, entries);
     middle_phone_number(&english) * middle_phone_number(&swedish) * middle_phone_number(&dutch)
 }
 
+/// prints the input as a directory for a locale, using the tailoring of its language if it has
+/// none of its own, and the DUCET if neither has one. rule files in `--tailorings DIR` take
+/// precedence over the bundled ones. numbers are read as numbers of `--region CC`, or of the
+/// locale's region, as `SE` in `sv-SE`, and listed as written if neither is given
+fn print_directory(input: &str, mut args: &[String]) {
+    let mut dir = None;
+    let mut region = None;
+    while let [flag, value, rest @ ..] = args {
+        match flag.as_str() {
+            "--tailorings" => dir = Some(value),
+            "--region" => region = Some(value.as_str()),
+            _ => break,
+        }
+        args = rest;
+    }
+    let (locale, format) = match args {
+        [locale] => (locale, Format::Text),
+        [locale, format] => (locale, format.parse().expect(DIRECTORY_USAGE)),
+        _ => panic!("{DIRECTORY_USAGE}"),
+    };
+    let tailored = |locale: &str| match dir {
+        Some(dir) => Collator::for_locale_in(locale, dir),
+        None => Collator::for_locale(locale),
+    };
+    let language = locale.split(['-', '_']).next().unwrap();
+    let collator = match tailored(locale) {
+        Err(TailoringError::UnknownLocale(_)) => tailored(language),
+        collator => collator,
+    };
+    let collator = match collator {
+        Err(TailoringError::UnknownLocale(_)) => Collator::ducet(),
+        collator => collator.unwrap(),
+    };
+    let convention = Convention::for_locale(locale);
+    let metadata = Metadata::bundled();
+    let region = region.or_else(|| {
+        locale
+            .split(['-', '_'])
+            .skip(1)
+            .find(|&subtag| subtag.len() == 2 && metadata.region(subtag).is_some())
+    });
+    print!(
+        "{}",
+        directory::render(
+            &parse(input),
+            &collator,
+            convention,
+            format,
+            &metadata,
+            region
+        )
+    );
+}
+
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
     let input = fs::read_to_string("input").unwrap();
+    if args.first().is_some_and(|command| command == "directory") {
+        return print_directory(&input, &args[1..]);
+    }
     let answer = solution(&input);
     println!("{answer}");
 }
@@ -77,13 +109,21 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::str::FromStr;
 
     const TEST_INPUT: &str = include_str!("../test-input");
 
+    fn lines(entries: &[DirectoryEntry], convention: Convention) -> Vec<String> {
+        entries.iter().map(|entry| entry.line(convention)).collect()
+    }
+
     #[test]
     fn test_sort_english() {
         assert_eq!(
-            sort_english(TEST_INPUT.lines().map(ToOwned::to_owned).collect()),
+            lines(
+                &sort_names(&Collator::ducet(), Convention::AsWritten, parse(TEST_INPUT)),
+                Convention::AsWritten
+            ),
             &[
                 "Aalto, Alvar: 0192872",
                 "Åberg, Rosa-Maria: 0110966",
@@ -107,7 +147,14 @@ mod tests {
     #[test]
     fn test_sort_swedish() {
         assert_eq!(
-            sort_swedish(TEST_INPUT.lines().map(ToOwned::to_owned).collect()),
+            lines(
+                &sort_names(
+                    &Collator::for_locale("sv").unwrap(),
+                    Convention::for_locale("sv"),
+                    parse(TEST_INPUT)
+                ),
+                Convention::AsWritten
+            ),
             &[
                 "Aalto, Alvar: 0192872",
                 "Navarrete Ortiz, Dolores: 0119411",
@@ -131,15 +178,21 @@ mod tests {
     #[test]
     fn test_move_dutch_infix() {
         assert_eq!(
-            move_dutch_infix("Aalto, Alvar: 0192872"),
+            DirectoryEntry::from_str("Aalto, Alvar: 0192872")
+                .unwrap()
+                .line(Convention::Dutch),
             "Aalto, Alvar: 0192872"
         );
         assert_eq!(
-            move_dutch_infix("van Leeuw, Floor: 0144158"),
+            DirectoryEntry::from_str("van Leeuw, Floor: 0144158")
+                .unwrap()
+                .line(Convention::Dutch),
             "Leeuw, Floor van: 0144158"
         );
         assert_eq!(
-            move_dutch_infix("van den Heyden, Harm: 0168131"),
+            DirectoryEntry::from_str("van den Heyden, Harm: 0168131")
+                .unwrap()
+                .line(Convention::Dutch),
             "Heyden, Harm van den: 0168131"
         );
     }
@@ -147,7 +200,10 @@ mod tests {
     #[test]
     fn test_sort_dutch() {
         assert_eq!(
-            sort_dutch(TEST_INPUT.lines().map(ToOwned::to_owned).collect()),
+            lines(
+                &sort_names(&Collator::ducet(), Convention::Dutch, parse(TEST_INPUT)),
+                Convention::Dutch
+            ),
             &[
                 "Aalto, Alvar: 0192872",
                 "Åberg, Rosa-Maria: 0110966",
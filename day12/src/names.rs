@@ -0,0 +1,314 @@
+use crate::collate::Collator;
+
+/// a personal name split the way a directory lists it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Name {
+    /// the family name without any leading particles, e.g. `Heyden`
+    pub surname: String,
+    /// the particles written in lowercase before the family name, e.g. `van den` or `al-`
+    pub infix: String,
+    pub given: String,
+}
+
+impl Name {
+    /// parses `Surname, Given`. everything before the first capital of the surname is taken as
+    /// its particles; a capitalised particle, as in `Van Damme`, is part of the surname, and a
+    /// surname with no capital at all is taken whole
+    pub fn parse(name: &str) -> Option<Self> {
+        let (family, given) = name.split_once(',').unwrap_or((name, ""));
+        let family = family.trim();
+        let start = family.find(char::is_uppercase).unwrap_or(0);
+        let (infix, surname) = family.split_at(start);
+        if surname.is_empty() {
+            return None;
+        }
+        Some(Self {
+            surname: surname.to_owned(),
+            infix: infix.trim_end().to_owned(),
+            given: given.trim().to_owned(),
+        })
+    }
+
+    /// the family name as written, particles and all. a particle ending in a hyphen or an
+    /// apostrophe, as in `al-Hassan` or `d'Artagnan`, is joined to the surname
+    pub fn family(&self) -> String {
+        if self.infix.is_empty() || self.infix.ends_with(['-', '\'', '’']) {
+            format!("{}{}", self.infix, self.surname)
+        } else {
+            format!("{} {}", self.infix, self.surname)
+        }
+    }
+}
+
+/// how a locale alphabetises family names
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Convention {
+    /// under the family name as written, particles and all, as in English or Flemish
+    AsWritten,
+    /// Dutch: particles such as `van` and `de` are ignored, under the next capitalised word
+    Dutch,
+    /// German: `von` and other particles are ignored
+    German,
+    /// French: the preposition `de` or `d'` is ignored, but articles such as `du` and `La` aren't
+    French,
+    /// Spanish: by the first surname, then the second, ignoring particles like `de la` before
+    /// either. a lone surname sorts before the same one followed by a second
+    Spanish,
+    /// Irish and Scottish: `Mc` sorts as `Mac`. `O'` needs no rewriting, as the collator already
+    /// ignores the apostrophe
+    Irish,
+    /// Arabic: the article `al-` or `el-` is ignored
+    Arabic,
+}
+
+fn strip_article(family: &str) -> &str {
+    ["al-", "el-", "al ", "el "]
+        .iter()
+        .find_map(|article| {
+            let prefix = family.get(..article.len())?;
+            prefix
+                .eq_ignore_ascii_case(article)
+                .then(|| &family[article.len()..])
+        })
+        .unwrap_or(family)
+}
+
+impl Convention {
+    /// the convention for a BCP 47 locale such as `nl` or `nl-BE`
+    pub fn for_locale(locale: &str) -> Self {
+        let mut subtags = locale.split(['-', '_']);
+        match (subtags.next().unwrap_or(""), subtags.next()) {
+            ("nl", Some("BE")) => Self::AsWritten,
+            ("nl", _) => Self::Dutch,
+            ("de", _) => Self::German,
+            ("fr", _) => Self::French,
+            ("es", _) => Self::Spanish,
+            ("ga" | "gd", _) | ("en", Some("IE")) => Self::Irish,
+            ("ar", _) => Self::Arabic,
+            _ => Self::AsWritten,
+        }
+    }
+
+    /// whether the particles are left out of the family name when sorting
+    fn ignores_infix(&self, name: &Name) -> bool {
+        match self {
+            Self::Dutch | Self::German | Self::Spanish | Self::Arabic => true,
+            Self::French => matches!(name.infix.as_str(), "de" | "d'" | "d’"),
+            Self::AsWritten | Self::Irish => false,
+        }
+    }
+
+    /// the parts of `name` to compare, most significant first
+    pub fn sort_fields(&self, name: &Name) -> Vec<String> {
+        let ignores_infix = self.ignores_infix(name);
+        let family = if ignores_infix {
+            name.surname.clone()
+        } else {
+            name.family()
+        };
+        let mut fields = match self {
+            Self::Spanish => {
+                let (first, second) = family.split_once(' ').unwrap_or((&family, ""));
+                let second = second.trim();
+                let second = &second[second.find(char::is_uppercase).unwrap_or(0)..];
+                vec![first.to_owned(), second.to_owned()]
+            }
+            Self::Irish => match family.strip_prefix("Mc") {
+                Some(rest) if rest.starts_with(char::is_uppercase) => vec![format!("Mac{rest}")],
+                _ => vec![family],
+            },
+            Self::Arabic => vec![strip_article(&family).to_owned()],
+            _ => vec![family],
+        };
+        fields.push(name.given.clone());
+        if ignores_infix {
+            fields.push(name.infix.clone());
+        }
+        fields
+    }
+
+    /// a key under which names sort by this convention and `collator`
+    pub fn sort_key(&self, name: &Name, collator: &Collator) -> Vec<Vec<u32>> {
+        self.sort_fields(name)
+            .iter()
+            .map(|field| collator.sort_key(field))
+            .collect()
+    }
+
+    /// `name` as a directory following this convention lists it, with ignored particles after
+    /// the given names: `Heyden, Harm van den`
+    pub fn format(&self, name: &Name) -> String {
+        let ignores_infix = self.ignores_infix(name);
+        let (family, mut given) = if ignores_infix {
+            (name.surname.clone(), name.given.clone())
+        } else {
+            (name.family(), name.given.clone())
+        };
+        if ignores_infix && !name.infix.is_empty() {
+            if !given.is_empty() {
+                given.push(' ');
+            }
+            given.push_str(&name.infix);
+        }
+        if given.is_empty() {
+            family
+        } else {
+            format!("{family}, {given}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted<'a>(convention: Convention, names: &[&'a str]) -> Vec<&'a str> {
+        let collator = Collator::ducet();
+        let mut names = names.to_vec();
+        names
+            .sort_by_cached_key(|name| convention.sort_key(&Name::parse(name).unwrap(), &collator));
+        names
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Name::parse("van den Heyden, Harm"),
+            Some(Name {
+                surname: "Heyden".to_owned(),
+                infix: "van den".to_owned(),
+                given: "Harm".to_owned(),
+            })
+        );
+        let name = Name::parse("al-Hassan, Omar").unwrap();
+        assert_eq!(
+            (name.infix.as_str(), name.family()),
+            ("al-", "al-Hassan".to_owned())
+        );
+        assert_eq!(Name::parse("Van Damme, Jean").unwrap().infix, "");
+        assert_eq!(Name::parse("Cher").unwrap().given, "");
+        assert_eq!(Name::parse("hooks, bell").unwrap().surname, "hooks");
+        assert_eq!(Name::parse(", Harm"), None);
+    }
+
+    #[test]
+    fn test_for_locale() {
+        assert_eq!(Convention::for_locale("nl"), Convention::Dutch);
+        assert_eq!(Convention::for_locale("nl-BE"), Convention::AsWritten);
+        assert_eq!(Convention::for_locale("en_IE"), Convention::Irish);
+        assert_eq!(Convention::for_locale("sv"), Convention::AsWritten);
+    }
+
+    #[test]
+    fn test_conventions() {
+        let names = [
+            "van Gogh, Vincent",
+            "Visser, Anna",
+            "Smit, Kees",
+            "de Boer, Jan",
+        ];
+        assert_eq!(
+            sorted(Convention::Dutch, &names),
+            [
+                "de Boer, Jan",
+                "van Gogh, Vincent",
+                "Smit, Kees",
+                "Visser, Anna"
+            ]
+        );
+        assert_eq!(
+            sorted(Convention::AsWritten, &names),
+            [
+                "de Boer, Jan",
+                "Smit, Kees",
+                "van Gogh, Vincent",
+                "Visser, Anna"
+            ]
+        );
+        assert_eq!(
+            sorted(
+                Convention::German,
+                &["von Goethe, Johann", "Fontane, Theodor"]
+            ),
+            ["Fontane, Theodor", "von Goethe, Johann"]
+        );
+        assert_eq!(
+            sorted(
+                Convention::French,
+                &["de Gaulle, Charles", "du Bellay, Joachim", "Fabre, Jean"]
+            ),
+            ["du Bellay, Joachim", "Fabre, Jean", "de Gaulle, Charles"]
+        );
+        assert_eq!(
+            sorted(
+                Convention::Spanish,
+                &["García Pérez, Ana", "García, Luis", "García López, Juan"]
+            ),
+            ["García, Luis", "García López, Juan", "García Pérez, Ana"]
+        );
+        assert_eq!(
+            sorted(
+                Convention::Spanish,
+                &[
+                    "García Zapata, José",
+                    "García de la Vega, Ana",
+                    "García Durán, Luis"
+                ]
+            ),
+            [
+                "García Durán, Luis",
+                "García de la Vega, Ana",
+                "García Zapata, José"
+            ]
+        );
+        assert_eq!(
+            sorted(
+                Convention::French,
+                &["d'Artagnan, Charles", "Balzac, Honoré", "Camus, Albert"]
+            ),
+            ["d'Artagnan, Charles", "Balzac, Honoré", "Camus, Albert"]
+        );
+        assert_eq!(
+            sorted(
+                Convention::Irish,
+                &[
+                    "MacNeill, Eoin",
+                    "McCarthy, Cormac",
+                    "Mackay, Ian",
+                    "O'Brien, Flann",
+                    "Oakes, Ann"
+                ]
+            ),
+            [
+                "McCarthy, Cormac",
+                "Mackay, Ian",
+                "MacNeill, Eoin",
+                "Oakes, Ann",
+                "O'Brien, Flann"
+            ]
+        );
+        assert_eq!(
+            sorted(
+                Convention::Arabic,
+                &["al-Rashid, Harun", "Haddad, Omar", "El-Baz, Farouk"]
+            ),
+            ["El-Baz, Farouk", "Haddad, Omar", "al-Rashid, Harun"]
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        let name = Name::parse("van den Heyden, Harm").unwrap();
+        assert_eq!(Convention::Dutch.format(&name), "Heyden, Harm van den");
+        assert_eq!(Convention::AsWritten.format(&name), "van den Heyden, Harm");
+        let name = Name::parse("d'Artagnan, Charles").unwrap();
+        assert_eq!(name.family(), "d'Artagnan");
+        assert_eq!(Convention::AsWritten.format(&name), "d'Artagnan, Charles");
+        assert_eq!(Convention::French.format(&name), "Artagnan, Charles d'");
+        assert_eq!(Name::parse("d’Artagnan").unwrap().family(), "d’Artagnan");
+        let name = Name::parse("de la Fuente, Juan").unwrap();
+        assert_eq!(Convention::Spanish.format(&name), "Fuente, Juan de la");
+        let name = Name::parse("García de la Vega, Ana").unwrap();
+        assert_eq!(Convention::Spanish.format(&name), "García de la Vega, Ana");
+    }
+}
@@ -0,0 +1,335 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PhoneError {
+    Empty,
+    /// a character other than digits, spaces and `+-.()/`
+    InvalidCharacter(char),
+    /// no country has the calling code an international number starts with
+    UnknownCallingCode,
+    /// there's no metadata for the region a national number was given for
+    UnknownRegion(String),
+    /// the national number has fewer digits than its country allows
+    TooShort,
+    TooLong,
+    /// the line of the metadata that couldn't be parsed
+    Metadata(usize),
+}
+
+impl fmt::Display for PhoneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("phone number has no digits"),
+            Self::InvalidCharacter(c) => write!(f, "invalid character {c:?} in phone number"),
+            Self::UnknownCallingCode => f.write_str("unknown country calling code"),
+            Self::UnknownRegion(region) => write!(f, "no phone number metadata for {region}"),
+            Self::TooShort => f.write_str("phone number too short for its country"),
+            Self::TooLong => f.write_str("phone number too long for its country"),
+            Self::Metadata(line) => write!(f, "invalid phone number metadata on line {line}"),
+        }
+    }
+}
+
+impl std::error::Error for PhoneError {}
+
+/// the characters a number may be written with between its digits, which parsing ignores
+pub const SEPARATORS: [char; 6] = [' ', '-', '.', '/', '(', ')'];
+
+/// how one country's numbers are dialled and written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Country {
+    /// the ISO 3166 code, e.g. `SE`
+    pub region: String,
+    pub calling_code: u16,
+    /// dialled before a national number within the country, e.g. `0`
+    pub trunk_prefix: Option<String>,
+    /// dialled before a calling code to call abroad, e.g. `00`
+    pub international_prefix: String,
+    /// the numbers of digits a national significant number may have
+    pub lengths: RangeInclusive<usize>,
+    /// the sizes of the groups of digits a number is written in, the last repeating
+    pub groups: Vec<usize>,
+}
+
+impl Country {
+    /// `national` split into its groups of digits
+    fn group<'a>(&self, national: &'a str) -> Vec<&'a str> {
+        let mut groups = Vec::new();
+        let mut rest = national;
+        let mut sizes = self
+            .groups
+            .iter()
+            .chain(std::iter::repeat(self.groups.last().unwrap()));
+        while !rest.is_empty() {
+            let size = (*sizes.next().unwrap()).min(rest.len());
+            let (group, after) = rest.split_at(size);
+            groups.push(group);
+            rest = after;
+        }
+        groups
+    }
+}
+
+/// the countries phone numbers can be parsed for and formatted in
+#[derive(Debug, Clone)]
+pub struct Metadata {
+    countries: Vec<Country>,
+}
+
+/// a number in E.164 terms: a country calling code and a national significant number, which has
+/// no trunk prefix
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PhoneNumber {
+    pub calling_code: u16,
+    pub national: String,
+}
+
+impl Metadata {
+    /// parses metadata in the format of the bundled `data/phone.txt`
+    pub fn parse(text: &str) -> Result<Self, PhoneError> {
+        let mut countries = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let country = Self::parse_country(line).ok_or(PhoneError::Metadata(i + 1))?;
+            countries.push(country);
+        }
+        Ok(Self { countries })
+    }
+
+    /// `SE 46 0 00 7-9 2,3,2`
+    fn parse_country(line: &str) -> Option<Country> {
+        let [region, code, trunk, international, lengths, groups] =
+            line.split_whitespace().collect::<Vec<_>>()[..]
+        else {
+            return None;
+        };
+        let (min, max) = lengths.split_once('-').unwrap_or((lengths, lengths));
+        let groups = groups
+            .split(',')
+            .map(|size| size.parse().ok().filter(|&size| size > 0))
+            .collect::<Option<Vec<usize>>>()?;
+        Some(Country {
+            region: region.to_owned(),
+            calling_code: code.parse().ok()?,
+            trunk_prefix: (trunk != "-").then(|| trunk.to_owned()),
+            international_prefix: international.to_owned(),
+            lengths: min.parse().ok()?..=max.parse().ok()?,
+            groups,
+        })
+    }
+
+    pub fn bundled() -> Self {
+        Self::parse(include_str!("../data/phone.txt")).unwrap()
+    }
+
+    pub fn region(&self, region: &str) -> Option<&Country> {
+        self.countries
+            .iter()
+            .find(|country| country.region.eq_ignore_ascii_case(region))
+    }
+
+    /// the first country with `calling_code`: of those sharing one, such as the US and Canada,
+    /// the one listed first
+    pub fn calling_code(&self, calling_code: u16) -> Option<&Country> {
+        self.countries
+            .iter()
+            .find(|country| country.calling_code == calling_code)
+    }
+
+    /// parses `text` as an international number, starting with `+` or the international prefix
+    /// of `region`, or otherwise as a national number of `region`, with or without its trunk
+    /// prefix. the [`SEPARATORS`] are ignored
+    pub fn parse_number(&self, text: &str, region: &str) -> Result<PhoneNumber, PhoneError> {
+        let home = self
+            .region(region)
+            .ok_or_else(|| PhoneError::UnknownRegion(region.to_owned()))?;
+        let text = text.trim();
+        let (plus, text) = match text.strip_prefix('+') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let mut digits = String::new();
+        for c in text.chars() {
+            match c {
+                '0'..='9' => digits.push(c),
+                _ if SEPARATORS.contains(&c) => {}
+                _ => return Err(PhoneError::InvalidCharacter(c)),
+            }
+        }
+        if digits.is_empty() {
+            return Err(PhoneError::Empty);
+        }
+        let international = if plus {
+            Some(digits.as_str())
+        } else {
+            digits.strip_prefix(home.international_prefix.as_str())
+        };
+        let (country, national) = match international {
+            Some(rest) => (1..=3)
+                .filter_map(|len| {
+                    let country = self.calling_code(rest.get(..len)?.parse().ok()?)?;
+                    Some((country, &rest[len..]))
+                })
+                .next()
+                .ok_or(PhoneError::UnknownCallingCode)?,
+            None => {
+                let national = home
+                    .trunk_prefix
+                    .as_deref()
+                    .and_then(|trunk| digits.strip_prefix(trunk))
+                    .unwrap_or(&digits);
+                (home, national)
+            }
+        };
+        if national.len() < *country.lengths.start() {
+            return Err(PhoneError::TooShort);
+        }
+        if national.len() > *country.lengths.end() {
+            return Err(PhoneError::TooLong);
+        }
+        Ok(PhoneNumber {
+            calling_code: country.calling_code,
+            national: national.to_owned(),
+        })
+    }
+}
+
+impl PhoneNumber {
+    /// `+46701234567`
+    pub fn e164(&self) -> String {
+        format!("+{}{}", self.calling_code, self.national)
+    }
+
+    /// as dialled within its country: `070 123 45 67`. a trunk prefix of `0` is written as part
+    /// of the first group, any other as a group of its own: `1 212 555 0123`
+    pub fn national(&self, metadata: &Metadata) -> String {
+        let Some(country) = metadata.calling_code(self.calling_code) else {
+            return self.national.clone();
+        };
+        let groups = country.group(&self.national).join(" ");
+        match country.trunk_prefix.as_deref() {
+            None => groups,
+            Some("0") => format!("0{groups}"),
+            Some(trunk) => format!("{trunk} {groups}"),
+        }
+    }
+
+    /// `+46 70 123 45 67`
+    pub fn international(&self, metadata: &Metadata) -> String {
+        match metadata.calling_code(self.calling_code) {
+            Some(country) => format!(
+                "+{} {}",
+                self.calling_code,
+                country.group(&self.national).join(" ")
+            ),
+            None => format!("+{} {}", self.calling_code, self.national),
+        }
+    }
+
+    /// an RFC 3966 URI: `tel:+46-70-123-45-67`
+    pub fn rfc3966(&self, metadata: &Metadata) -> String {
+        match metadata.calling_code(self.calling_code) {
+            Some(country) => format!(
+                "tel:+{}-{}",
+                self.calling_code,
+                country.group(&self.national).join("-")
+            ),
+            None => format!("tel:{}", self.e164()),
+        }
+    }
+}
+
+impl fmt::Display for PhoneNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.e164())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_number() {
+        let metadata = Metadata::bundled();
+        let swedish = PhoneNumber {
+            calling_code: 46,
+            national: "701234567".to_owned(),
+        };
+        for text in [
+            "070-123 45 67",
+            "+46 70 123 45 67",
+            "0046701234567",
+            "(070) 123.45.67",
+        ] {
+            assert_eq!(
+                metadata.parse_number(text, "SE"),
+                Ok(swedish.clone()),
+                "{text}"
+            );
+        }
+        assert_eq!(
+            metadata.parse_number("+46 70 123 45 67", "US"),
+            Ok(swedish.clone())
+        );
+        assert_eq!(
+            metadata.parse_number("011 46 70 123 45 67", "US"),
+            Ok(swedish)
+        );
+        let us = metadata.parse_number("(212) 555-0123", "us").unwrap();
+        assert_eq!(us.e164(), "+12125550123");
+        let spanish = metadata.parse_number("912 345 678", "ES").unwrap();
+        assert_eq!(spanish.e164(), "+34912345678");
+    }
+
+    #[test]
+    fn test_invalid_numbers() {
+        let metadata = Metadata::bundled();
+        let errors = [
+            ("", "SE", PhoneError::Empty),
+            ("070 123 45 67 ext", "SE", PhoneError::InvalidCharacter('e')),
+            ("+999 1234567", "SE", PhoneError::UnknownCallingCode),
+            ("070 12", "SE", PhoneError::TooShort),
+            ("+31 6 1234 56789", "SE", PhoneError::TooLong),
+            (
+                "0701234567",
+                "XX",
+                PhoneError::UnknownRegion("XX".to_owned()),
+            ),
+        ];
+        for (text, region, error) in errors {
+            assert_eq!(metadata.parse_number(text, region), Err(error), "{text}");
+        }
+        assert_eq!(
+            Metadata::parse("SE 46 0 00 7-9\n").unwrap_err(),
+            PhoneError::Metadata(1)
+        );
+        assert_eq!(
+            Metadata::parse("# comment\nSE 46 0 00 x 2\n").unwrap_err(),
+            PhoneError::Metadata(2)
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        let metadata = Metadata::bundled();
+        let number = metadata.parse_number("070-123 45 67", "SE").unwrap();
+        assert_eq!(number.national(&metadata), "070 123 45 67");
+        assert_eq!(number.international(&metadata), "+46 70 123 45 67");
+        assert_eq!(number.rfc3966(&metadata), "tel:+46-70-123-45-67");
+        assert_eq!(number.to_string(), "+46701234567");
+        let french = metadata.parse_number("+33612345678", "SE").unwrap();
+        assert_eq!(french.national(&metadata), "06 12 34 56 78");
+        assert_eq!(french.international(&metadata), "+33 6 12 34 56 78");
+        let american = metadata.parse_number("(212) 555-0123", "US").unwrap();
+        assert_eq!(american.national(&metadata), "1 212 555 0123");
+        assert_eq!(american.international(&metadata), "+1 212 555 0123");
+        assert_eq!(american.rfc3966(&metadata), "tel:+1-212-555-0123");
+        let spanish = metadata.parse_number("912 345 678", "ES").unwrap();
+        assert_eq!(spanish.national(&metadata), "912 34 56 78");
+    }
+}
@@ -0,0 +1,176 @@
+use crate::collate::{Collator, Strength, key_of};
+use std::cmp::Ordering;
+use unicode_normalization::char::is_combining_mark;
+
+/// the number of primary weights in a sort key
+fn primaries_of(key: &[u32]) -> usize {
+    key.iter()
+        .position(|&weight| weight == 0)
+        .unwrap_or(key.len())
+}
+
+impl Collator {
+    /// whether `a` and `b` collate the same at `strength`. at [`Strength::Primary`], "Ostergard"
+    /// equals "Østergård"
+    pub fn equal_at(&self, a: &str, b: &str, strength: Strength) -> bool {
+        self.sort_key_at(a, strength) == self.sort_key_at(b, strength)
+    }
+
+    /// the length in bytes of the shortest start of `text` that collates the same as `prefix` at
+    /// `strength`, taking in any accents after it that `strength` ignores, or `None` if no start
+    /// of it does. a start can't end inside a contraction, so in Czech "c" doesn't start "Chalupa"
+    pub fn match_prefix(&self, text: &str, prefix: &str, strength: Strength) -> Option<usize> {
+        let wanted = self.sort_key_at(prefix, strength);
+        let weights = self.weights(text);
+        let mut matched = None;
+        let mut primaries = 0;
+        for k in 0..=weights.len() {
+            if k > 0 && weights[k - 1].0[0] != 0 {
+                primaries += 1;
+            }
+            let end = k.checked_sub(1).map_or(0, |i| weights[i].1);
+            if k > 0 && weights.get(k).is_some_and(|&(_, next)| next == end) {
+                continue;
+            }
+            let last = text[..end].chars().next_back();
+            if matched.is_some() && !last.is_some_and(is_combining_mark) {
+                break;
+            }
+            // only a start with as many primary weights can match, so the key is built only then
+            if primaries == primaries_of(&wanted) && key_of(&weights[..k], strength) == wanted {
+                matched = Some(end);
+            } else if matched.is_some() || primaries > primaries_of(&wanted) {
+                break;
+            }
+        }
+        matched
+    }
+
+    pub fn starts_with(&self, text: &str, prefix: &str, strength: Strength) -> bool {
+        self.match_prefix(text, prefix, strength).is_some()
+    }
+
+    /// the entries of `texts` that start with `prefix` at `strength`
+    pub fn search<'a, S: AsRef<str>>(
+        &self,
+        texts: &'a [S],
+        prefix: &str,
+        strength: Strength,
+    ) -> Vec<&'a str> {
+        texts
+            .iter()
+            .map(AsRef::as_ref)
+            .filter(|text| self.starts_with(text, prefix, strength))
+            .collect()
+    }
+
+    /// where `text` belongs in `sorted`, which is in this collator's order: before any entry
+    /// collating the same at `strength`. the first entry of the page of a directory starting at
+    /// "M" is at `insertion_point(&sorted, "M", Strength::Primary)`
+    pub fn insertion_point<S: AsRef<str>>(
+        &self,
+        sorted: &[S],
+        text: &str,
+        strength: Strength,
+    ) -> usize {
+        let key = self.sort_key_at(text, strength);
+        sorted.partition_point(|entry| {
+            self.sort_key_at(entry.as_ref(), strength).cmp(&key) == Ordering::Less
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_equal_at() {
+        let collator = Collator::ducet();
+        assert!(collator.equal_at("Ostergard", "Østergård", Strength::Primary));
+        assert!(!collator.equal_at("Ostergard", "Østergård", Strength::Secondary));
+        assert!(collator.equal_at("resume", "RESUME", Strength::Secondary));
+        assert!(!collator.equal_at("resume", "RESUME", Strength::Tertiary));
+        assert!(collator.equal_at("de luca", "deluca", Strength::Tertiary));
+        assert!(!collator.equal_at("de luca", "deluca", Strength::Quaternary));
+        let sv = Collator::for_locale("sv").unwrap();
+        assert!(!sv.equal_at("Ostergard", "Østergård", Strength::Primary));
+    }
+
+    #[test]
+    fn test_match_prefix() {
+        let collator = Collator::ducet();
+        assert_eq!(
+            collator.match_prefix("Østergård", "oster", Strength::Primary),
+            Some("Øster".len())
+        );
+        assert_eq!(
+            collator.match_prefix("Østergård", "oster", Strength::Secondary),
+            None
+        );
+        assert_eq!(
+            collator.match_prefix("Cafe\u{301} au lait", "cafe", Strength::Primary),
+            Some("Cafe\u{301}".len())
+        );
+        assert_eq!(
+            collator.match_prefix("Aalto", "", Strength::Primary),
+            Some(0)
+        );
+        assert_eq!(
+            collator.match_prefix("a\u{316}\u{301}b", "á", Strength::Secondary),
+            None
+        );
+        assert_eq!(
+            collator.match_prefix("a\u{301}\u{316}b", "a\u{316}", Strength::Secondary),
+            None
+        );
+        assert_eq!(
+            collator.match_prefix("a\u{301}\u{316}b", "a\u{316}\u{301}", Strength::Secondary),
+            Some(5)
+        );
+        let cs = Collator::for_locale("cs").unwrap();
+        assert_eq!(cs.match_prefix("Chalupa", "c", Strength::Primary), None);
+        assert_eq!(cs.match_prefix("Chalupa", "ch", Strength::Primary), Some(2));
+        let names = [
+            "Østergård, Magnus",
+            "Olofsson, Mikael",
+            "O'Neill, Cara",
+            "Ørsted",
+        ];
+        assert_eq!(
+            collator.search(&names, "ost", Strength::Primary),
+            ["Østergård, Magnus"]
+        );
+        assert_eq!(
+            collator.search(&names, "on", Strength::Primary),
+            ["O'Neill, Cara"]
+        );
+    }
+
+    #[test]
+    fn test_insertion_point() {
+        let collator = Collator::ducet();
+        let sorted = [
+            "Aalto",
+            "Åberg",
+            "Ämtler",
+            "Navarrete",
+            "Olofsson",
+            "Zondervan",
+        ];
+        let point = |text| collator.insertion_point(&sorted, text, Strength::Tertiary);
+        assert_eq!(point("M"), 3);
+        assert_eq!(point("Aalto"), 0);
+        assert_eq!(point("Ab"), 1);
+        assert_eq!(point("Zz"), 6);
+        assert_eq!(point("ÅBERG"), 2);
+        assert_eq!(
+            collator.insertion_point(&sorted, "ÅBERG", Strength::Primary),
+            1
+        );
+        assert_eq!(
+            collator.insertion_point(&sorted, "amtler", Strength::Primary),
+            2
+        );
+    }
+}
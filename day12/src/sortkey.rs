@@ -0,0 +1,151 @@
+use crate::collate::{Collator, WEIGHT_SHIFT};
+use crate::names::{Convention, Name};
+
+/// ends each field of a multi-field key, below everything else so a shorter field sorts first
+pub const FIELD_SEPARATOR: u8 = 0;
+/// ends each level of a key, below the first byte of any weight
+pub const LEVEL_SEPARATOR: u8 = 1;
+
+/// appends `value` so that encodings compare bytewise as their values do and none is a prefix of
+/// another. small values take one byte; everything up to 67823 takes at most three
+fn push_varint(bytes: &mut Vec<u8>, value: u32) {
+    match value {
+        0..=240 => bytes.push(value as u8),
+        241..=2287 => {
+            let value = value - 240;
+            bytes.extend([241 + (value >> 8) as u8, value as u8]);
+        }
+        2288..=67823 => {
+            let value = value - 2288;
+            bytes.extend([249, (value >> 8) as u8, value as u8]);
+        }
+        _ if value < 1 << 24 => {
+            bytes.push(250);
+            bytes.extend(&value.to_be_bytes()[1..]);
+        }
+        _ => {
+            bytes.push(251);
+            bytes.extend(value.to_be_bytes());
+        }
+    }
+}
+
+/// appends `weight` as its DUCET weight, offset so its first byte is above both separators and
+/// doubled to make room for a flag saying whether a tailored fraction below [`WEIGHT_SHIFT`]
+/// follows. the fraction is zero for all but tailored weights, so most take a single varint
+fn push_weight(bytes: &mut Vec<u8>, weight: u32) {
+    let fraction = weight & ((1 << WEIGHT_SHIFT) - 1);
+    push_varint(
+        bytes,
+        ((weight >> WEIGHT_SHIFT) + 1) * 2 + u32::from(fraction != 0),
+    );
+    if fraction != 0 {
+        push_varint(bytes, fraction);
+    }
+}
+
+/// encodes a key from [`Collator::sort_key`] as bytes that compare with `memcmp` as it does
+pub fn encode(key: &[u32], bytes: &mut Vec<u8>) {
+    for &weight in key {
+        if weight == 0 {
+            bytes.push(LEVEL_SEPARATOR);
+        } else {
+            push_weight(bytes, weight);
+        }
+    }
+}
+
+impl Collator {
+    /// [`Collator::sort_key`] as compact bytes, for storing in a database index or a sorted file
+    /// where only bytewise comparison is available
+    pub fn byte_key(&self, text: &str) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        encode(&self.sort_key(text), &mut bytes);
+        bytes
+    }
+}
+
+impl Convention {
+    /// [`Convention::sort_key`] as bytes, each field followed by [`FIELD_SEPARATOR`]
+    pub fn byte_key(&self, name: &Name, collator: &Collator) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for field in self.sort_fields(name) {
+            encode(&collator.sort_key(&field), &mut bytes);
+            bytes.push(FIELD_SEPARATOR);
+        }
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint() {
+        let values = [0, 1, 240, 241, 2287, 2288, 67823, 67824, 1 << 24, u32::MAX];
+        let encoded: Vec<Vec<u8>> = values
+            .iter()
+            .map(|&value| {
+                let mut bytes = Vec::new();
+                push_varint(&mut bytes, value);
+                bytes
+            })
+            .collect();
+        assert!(encoded.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(encoded[2], [240]);
+        assert_eq!(encoded[6].len(), 3);
+        assert_eq!(encoded[9].len(), 5);
+    }
+
+    #[test]
+    fn test_byte_key() {
+        let collator = Collator::for_locale("sv").unwrap();
+        let words = [
+            "", "a", "A", "ab", "Ab", "å", "Å", "ä", "z", "de luca", "deluca", "de-luca", "ö", "ø",
+            "中", "中国", "résumé", "resume", "Résumé",
+        ];
+        for a in words {
+            for b in words {
+                assert_eq!(
+                    collator.byte_key(a).cmp(&collator.byte_key(b)),
+                    collator.sort_key(a).cmp(&collator.sort_key(b)),
+                    "{a} {b}"
+                );
+            }
+        }
+        assert_eq!(collator.byte_key("resume").len(), 6 * 3 + 1 + 6 + 1 + 6);
+    }
+
+    #[test]
+    fn test_name_byte_key() {
+        let collator = Collator::ducet();
+        let names = [
+            "García, Luis",
+            "García López, Juan",
+            "Garcíal, Ana",
+            "van den Heyden, Harm",
+            "Heyden, Harm",
+            "Heyden, Harmen",
+        ];
+        for convention in [
+            Convention::AsWritten,
+            Convention::Dutch,
+            Convention::Spanish,
+        ] {
+            for a in names {
+                for b in names {
+                    let (a, b) = (Name::parse(a).unwrap(), Name::parse(b).unwrap());
+                    assert_eq!(
+                        convention
+                            .byte_key(&a, &collator)
+                            .cmp(&convention.byte_key(&b, &collator)),
+                        convention
+                            .sort_key(&a, &collator)
+                            .cmp(&convention.sort_key(&b, &collator))
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,398 @@
+use crate::collate::{Collator, WEIGHT_SHIFT};
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::{fmt, fs, io};
+use unicode_normalization::UnicodeNormalization;
+
+/// the rules for each locale, built into the binary from `data/tailorings/<locale>.txt`
+const BUNDLED: &[(&str, &str)] = &[
+    ("cs", include_str!("../data/tailorings/cs.txt")),
+    ("da", include_str!("../data/tailorings/da.txt")),
+    (
+        "de-phonebook",
+        include_str!("../data/tailorings/de-phonebook.txt"),
+    ),
+    (
+        "es-traditional",
+        include_str!("../data/tailorings/es-traditional.txt"),
+    ),
+    ("nb", include_str!("../data/tailorings/nb.txt")),
+    ("sv", include_str!("../data/tailorings/sv.txt")),
+    ("tr", include_str!("../data/tailorings/tr.txt")),
+];
+
+/// the bundled rules for `locale`, if there are any
+pub fn bundled(locale: &str) -> Option<&'static str> {
+    BUNDLED
+        .iter()
+        .find(|&&(name, _)| name == locale)
+        .map(|&(_, rules)| rules)
+}
+
+#[derive(Debug)]
+pub enum TailoringError {
+    Io(io::Error),
+    /// no rules are bundled for the locale, nor found in the directory searched
+    UnknownLocale(String),
+    /// the byte offset in the rules at which they stopped making sense
+    Syntax(usize, &'static str),
+}
+
+impl fmt::Display for TailoringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::UnknownLocale(locale) => write!(f, "no collation rules for {locale}"),
+            Self::Syntax(offset, message) => write!(f, "{message} at offset {offset}"),
+        }
+    }
+}
+
+impl std::error::Error for TailoringError {}
+
+impl From<io::Error> for TailoringError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// how far a tailored string sorts from the one before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+    /// `<`, a different base letter
+    Primary,
+    /// `<<`, a different accent
+    Secondary,
+    /// `<<<`, a different case or variant
+    Tertiary,
+    /// `=`, no difference at all
+    Identical,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub relation: Relation,
+    pub text: String,
+    /// the `/` extension, whose elements are appended to the tailored string's
+    pub extension: Option<String>,
+}
+
+/// a reset and the strings placed relative to it, one `&` clause of a rule set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub reset: String,
+    /// `[before n]`, placing the first step before the reset rather than after it
+    pub before: Option<Relation>,
+    pub steps: Vec<Step>,
+}
+
+fn is_syntax(c: char) -> bool {
+    matches!(c, '&' | '<' | '=' | '/' | '[' | ']' | '#') || c.is_whitespace()
+}
+
+/// parses CLDR collation rule syntax, such as `&z < å <<< Å < ä <<< Ä`. `#` starts a comment
+/// running to the end of the line. quoting, escapes, prefixes and `*` ranges aren't supported
+pub fn parse(rules: &str) -> Result<Vec<Rule>, TailoringError> {
+    let text: String = rules
+        .lines()
+        .map(|line| line.split('#').next().unwrap())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut rules = Vec::new();
+    let mut rest = text.trim_start();
+    let offset = |rest: &str| text.len() - rest.len();
+    let word = |rest: &str| -> Result<(String, usize), TailoringError> {
+        let len = rest.find(is_syntax).unwrap_or(rest.len());
+        if len == 0 {
+            return Err(TailoringError::Syntax(offset(rest), "expected a string"));
+        }
+        Ok((rest[..len].to_owned(), len))
+    };
+    while !rest.is_empty() {
+        rest = rest
+            .strip_prefix('&')
+            .ok_or(TailoringError::Syntax(offset(rest), "expected '&'"))?
+            .trim_start();
+        let mut before = None;
+        if let Some(option) = rest.strip_prefix("[before ") {
+            let (level, after) = option
+                .split_once(']')
+                .ok_or(TailoringError::Syntax(offset(rest), "unterminated option"))?;
+            before = Some(match level.trim() {
+                "1" => Relation::Primary,
+                "2" => Relation::Secondary,
+                "3" => Relation::Tertiary,
+                _ => return Err(TailoringError::Syntax(offset(rest), "invalid level")),
+            });
+            rest = after.trim_start();
+        }
+        let (reset, len) = word(rest)?;
+        rest = rest[len..].trim_start();
+        let mut steps = Vec::new();
+        while !rest.is_empty() && !rest.starts_with('&') {
+            let (relation, len) = if rest.starts_with("<<<") {
+                (Relation::Tertiary, 3)
+            } else if rest.starts_with("<<") {
+                (Relation::Secondary, 2)
+            } else if rest.starts_with('<') {
+                (Relation::Primary, 1)
+            } else if rest.starts_with('=') {
+                (Relation::Identical, 1)
+            } else {
+                return Err(TailoringError::Syntax(offset(rest), "expected a relation"));
+            };
+            rest = rest[len..].trim_start();
+            let (text, len) = word(rest)?;
+            rest = rest[len..].trim_start();
+            let mut extension = None;
+            if let Some(after) = rest.strip_prefix('/') {
+                rest = after.trim_start();
+                let (text, len) = word(rest)?;
+                extension = Some(text);
+                rest = rest[len..].trim_start();
+            }
+            steps.push(Step {
+                relation,
+                text,
+                extension,
+            });
+        }
+        if before.is_some_and(|level| steps.first().is_none_or(|step| step.relation != level)) {
+            return Err(TailoringError::Syntax(
+                offset(rest),
+                "[before n] must be followed by a relation of the same level",
+            ));
+        }
+        rules.push(Rule {
+            reset,
+            before,
+            steps,
+        });
+    }
+    Ok(rules)
+}
+
+/// the weights in use at each level, for finding room between them
+struct Weights([BTreeSet<u32>; 3]);
+
+impl Weights {
+    fn new(collator: &Collator) -> Self {
+        let mut levels: [BTreeSet<u32>; 3] = Default::default();
+        for element in collator.entries.values().flatten() {
+            levels[0].insert(element.primary);
+            levels[1].insert(element.secondary);
+            levels[2].insert(element.tertiary);
+        }
+        Self(levels)
+    }
+
+    /// a new weight straight after `weight` at `level`
+    fn after(&mut self, level: usize, weight: u32) -> u32 {
+        let next = self.0[level]
+            .range(weight + 1..)
+            .next()
+            .copied()
+            .unwrap_or(weight.saturating_add(1 << WEIGHT_SHIFT));
+        let between = weight + (next - weight) / 2;
+        self.0[level].insert(between);
+        between
+    }
+
+    /// the weight in use straight before `weight` at `level`
+    fn before(&self, level: usize, weight: u32) -> u32 {
+        self.0[level]
+            .range(..weight)
+            .next_back()
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+fn level(relation: Relation) -> Option<usize> {
+    match relation {
+        Relation::Primary => Some(0),
+        Relation::Secondary => Some(1),
+        Relation::Tertiary => Some(2),
+        Relation::Identical => None,
+    }
+}
+
+const COMMON_SECONDARY: u32 = 0x0020 << WEIGHT_SHIFT;
+const COMMON_TERTIARY: u32 = 0x0002 << WEIGHT_SHIFT;
+
+impl Collator {
+    /// applies `rules` in order, each step placing its string straight after the previous one
+    /// and before anything that followed it at that level
+    pub fn tailor(&mut self, rules: &[Rule]) {
+        let mut weights = Weights::new(self);
+        for rule in rules {
+            let mut previous = self.elements(&rule.reset);
+            if let (Some(level), Some(last)) = (rule.before.and_then(level), previous.last_mut()) {
+                let weight = match level {
+                    0 => &mut last.primary,
+                    1 => &mut last.secondary,
+                    _ => &mut last.tertiary,
+                };
+                *weight = weights.before(level, *weight);
+            }
+            for step in &rule.steps {
+                let mut elements = previous.clone();
+                if let Some(last) = elements.last_mut() {
+                    match level(step.relation) {
+                        Some(0) => {
+                            last.primary = weights.after(0, last.primary);
+                            last.secondary = COMMON_SECONDARY;
+                            last.tertiary = COMMON_TERTIARY;
+                        }
+                        Some(1) => {
+                            last.secondary = weights.after(1, last.secondary);
+                            last.tertiary = COMMON_TERTIARY;
+                        }
+                        Some(_) => last.tertiary = weights.after(2, last.tertiary),
+                        None => {}
+                    }
+                    last.variable = false;
+                }
+                previous = elements.clone();
+                if let Some(extension) = &step.extension {
+                    elements.extend(self.elements(extension));
+                }
+                self.insert(step.text.nfd().collect(), elements);
+            }
+        }
+    }
+
+    pub fn tailored(mut self, rules: &str) -> Result<Self, TailoringError> {
+        self.tailor(&parse(rules)?);
+        Ok(self)
+    }
+
+    /// the DUCET tailored by the rule file at `path`
+    pub fn load_tailoring(path: impl AsRef<Path>) -> Result<Self, TailoringError> {
+        Self::ducet().tailored(&fs::read_to_string(path)?)
+    }
+
+    /// the DUCET tailored for `locale`, such as `sv` or `de-phonebook`, from the bundled rules
+    pub fn for_locale(locale: &str) -> Result<Self, TailoringError> {
+        let rules =
+            bundled(locale).ok_or_else(|| TailoringError::UnknownLocale(locale.to_owned()))?;
+        Self::ducet().tailored(rules)
+    }
+
+    /// like `for_locale`, but a `<locale>.txt` in `dir` takes precedence over the bundled rules,
+    /// so locales can be added or overridden without rebuilding
+    pub fn for_locale_in(locale: &str, dir: impl AsRef<Path>) -> Result<Self, TailoringError> {
+        match fs::read_to_string(dir.as_ref().join(format!("{locale}.txt"))) {
+            Ok(rules) => Self::ducet().tailored(&rules),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Self::for_locale(locale),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collate::Element;
+
+    fn sorted<'a>(collator: &Collator, words: &[&'a str]) -> Vec<&'a str> {
+        let mut words = words.to_vec();
+        words.sort_by(|a, b| collator.compare(a, b));
+        words
+    }
+
+    #[test]
+    fn test_parse() {
+        let rules = parse("&z < å <<< Å # comment\n&t <<< þ/h &[before 1]i < ı").unwrap();
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[0].reset, "z");
+        assert_eq!(
+            rules[0].steps[1],
+            Step {
+                relation: Relation::Tertiary,
+                text: "Å".to_owned(),
+                extension: None
+            }
+        );
+        assert_eq!(rules[1].steps[0].extension.as_deref(), Some("h"));
+        assert_eq!(rules[2].before, Some(Relation::Primary));
+        assert!(parse("z < å").is_err());
+        assert!(parse("&z <").is_err());
+        assert!(parse("&z ! å").is_err());
+        assert!(parse("&[before 1]i << ı").is_err());
+    }
+
+    #[test]
+    fn test_tailor() {
+        let collator = Collator::ducet()
+            .tailored("&z < å <<< Å < ä &c < ch")
+            .unwrap();
+        assert_eq!(
+            sorted(&collator, &["ä", "Å", "zz", "å", "ch", "cz", "d"]),
+            ["cz", "ch", "d", "zz", "å", "Å", "ä"]
+        );
+        let first: Vec<Element> = collator.elements("å");
+        assert_eq!(first.len(), 1);
+    }
+
+    #[test]
+    fn test_locales() {
+        let words = [
+            "Öberg", "Zorn", "Åkesson", "Ærø", "Ahlberg", "Østberg", "Ärlig",
+        ];
+        let sv = Collator::for_locale("sv").unwrap();
+        assert_eq!(
+            sorted(&sv, &words),
+            [
+                "Ahlberg", "Zorn", "Åkesson", "Ärlig", "Ærø", "Öberg", "Østberg"
+            ]
+        );
+        let da = Collator::for_locale("da").unwrap();
+        assert_eq!(
+            sorted(&da, &["Aarhus", "Zealand", "Århus", "Ørsted", "Abildgaard"]),
+            ["Abildgaard", "Zealand", "Ørsted", "Århus", "Aarhus"]
+        );
+        let nb = Collator::for_locale("nb").unwrap();
+        assert_eq!(sorted(&nb, &["å", "æ", "ø", "z"]), ["z", "æ", "ø", "å"]);
+        let de = Collator::for_locale("de-phonebook").unwrap();
+        assert_eq!(
+            sorted(&de, &["Müller", "Muff", "Mueller"]),
+            ["Mueller", "Müller", "Muff"]
+        );
+        let es = Collator::for_locale("es-traditional").unwrap();
+        assert_eq!(
+            sorted(&es, &["llama", "luz", "chico", "cosa", "ñu", "nube"]),
+            ["cosa", "chico", "luz", "llama", "nube", "ñu"]
+        );
+        let cs = Collator::for_locale("cs").unwrap();
+        assert_eq!(
+            sorted(&cs, &["chata", "hora", "čas", "cena", "iva"]),
+            ["cena", "čas", "hora", "chata", "iva"]
+        );
+        let tr = Collator::for_locale("tr").unwrap();
+        assert_eq!(
+            sorted(&tr, &["ıhlamur", "İzmir", "Irmak", "ilk", "çay", "cam"]),
+            ["cam", "çay", "ıhlamur", "Irmak", "ilk", "İzmir"]
+        );
+        assert!(matches!(
+            Collator::for_locale("xx"),
+            Err(TailoringError::UnknownLocale(_))
+        ));
+    }
+
+    #[test]
+    fn test_extra_directory() {
+        let dir = std::env::temp_dir().join(format!("day12-{}-tailorings", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("xx.txt"), "&z < å").unwrap();
+        let xx = Collator::for_locale_in("xx", &dir).unwrap();
+        assert_eq!(sorted(&xx, &["å", "z", "a"]), ["a", "z", "å"]);
+        let sv = Collator::for_locale_in("sv", &dir).unwrap();
+        assert_eq!(sorted(&sv, &["ö", "z", "ä"]), ["z", "ä", "ö"]);
+        assert!(matches!(
+            Collator::for_locale_in("yy", &dir),
+            Err(TailoringError::UnknownLocale(_))
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
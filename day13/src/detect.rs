@@ -0,0 +1,310 @@
+use crate::encoding::{self, Encoding};
+use std::collections::HashMap;
+
+/// how much of a language model's probability comes from its corpus rather than the background
+const CORPUS_WEIGHT: f64 = 0.95;
+
+/// the probability of `c` in text of no particular language: a share for its block of Unicode,
+/// spread evenly over the block. control characters and private use are all but ruled out
+fn background(c: char) -> f64 {
+    let (share, size) = match c {
+        '\t' | '\n' | '\r' => (0.02, 3),
+        ' '..='~' => (0.4, 95),
+        '\0'..='\u{1f}' | '\u{7f}'..='\u{9f}' => (1e-9, 65),
+        '\u{a0}'..='\u{ff}' => (0.05, 96),
+        '\u{100}'..='\u{24f}' | '\u{1e00}'..='\u{1eff}' => (0.05, 592),
+        '\u{300}'..='\u{36f}' => (0.005, 112),
+        '\u{370}'..='\u{3ff}' => (0.03, 144),
+        '\u{400}'..='\u{4ff}' => (0.03, 256),
+        '\u{590}'..='\u{5ff}' => (0.02, 112),
+        '\u{600}'..='\u{6ff}' => (0.02, 256),
+        '\u{2000}'..='\u{206f}' => (0.01, 112),
+        '\u{3000}'..='\u{303f}' => (0.01, 64),
+        '\u{3040}'..='\u{30ff}' => (0.03, 192),
+        '\u{4e00}'..='\u{9fff}' => (0.08, 20992),
+        '\u{ac00}'..='\u{d7a3}' => (0.02, 11172),
+        '\u{e000}'..='\u{f8ff}' => (1e-7, 6400),
+        '\u{ff00}'..='\u{ffef}' => (0.005, 240),
+        _ => (0.005, 50000),
+    };
+    share / f64::from(size)
+}
+
+/// character frequencies for one language, trained from sample text
+#[derive(Debug, Clone)]
+pub struct LanguageModel {
+    pub name: String,
+    frequencies: HashMap<char, f64>,
+}
+
+impl LanguageModel {
+    pub fn train(name: &str, corpus: &str) -> Self {
+        let mut counts: HashMap<char, f64> = HashMap::new();
+        for c in corpus.chars() {
+            *counts.entry(c).or_default() += 1.0;
+        }
+        let total: f64 = counts.values().sum();
+        for count in counts.values_mut() {
+            *count /= total;
+        }
+        Self {
+            name: name.to_owned(),
+            frequencies: counts,
+        }
+    }
+
+    /// models for the languages of each family of legacy encodings, trained on the bundled corpora
+    pub fn bundled() -> Vec<Self> {
+        macro_rules! corpus {
+            ($name:literal, $file:literal) => {
+                Self::train($name, include_str!(concat!("../corpora/", $file, ".txt")))
+            };
+        }
+        vec![
+            Self::train("English", corpora::ENGLISH),
+            corpus!("German", "german"),
+            corpus!("French", "french"),
+            corpus!("Spanish", "spanish"),
+            corpus!("Swedish", "swedish"),
+            corpus!("Finnish", "finnish"),
+            corpus!("Polish", "polish"),
+            corpus!("Czech", "czech"),
+            corpus!("Hungarian", "hungarian"),
+            corpus!("Turkish", "turkish"),
+            corpus!("Lithuanian", "lithuanian"),
+            corpus!("Latvian", "latvian"),
+            corpus!("Estonian", "estonian"),
+            corpus!("Vietnamese", "vietnamese"),
+            Self::train("Greek", corpora::GREEK),
+            Self::train("Russian", corpora::RUSSIAN),
+            corpus!("Hebrew", "hebrew"),
+            corpus!("Arabic", "arabic"),
+            corpus!("Japanese", "japanese"),
+            corpus!("Chinese (simplified)", "chinese-simplified"),
+            corpus!("Chinese (traditional)", "chinese-traditional"),
+        ]
+    }
+
+    /// the log likelihood of `text` under this model
+    pub fn score(&self, text: &str) -> f64 {
+        text.chars()
+            .map(|c| {
+                let frequency = self.frequencies.get(&c).copied().unwrap_or(0.0);
+                (CORPUS_WEIGHT * frequency + (1.0 - CORPUS_WEIGHT) * background(c)).ln()
+            })
+            .sum()
+    }
+}
+
+/// the log prior of each encoding: how likely it is before looking at the text. the Unicode
+/// encodings without a byte order mark are less common than UTF-8, and only the most widespread
+/// legacy encodings are favoured
+fn prior(encoding: Encoding) -> f64 {
+    match encoding {
+        Encoding::Utf8 => 0.0,
+        Encoding::Utf16(_) => -1.0,
+        Encoding::Utf32(_) => -2.0,
+        Encoding::Legacy(encoding) if encoding == encoding_rs::WINDOWS_1252 => -0.5,
+        Encoding::Legacy(_) => -1.5,
+    }
+}
+
+/// `bytes` decoded, or if `partial`, as many of them as are valid once up to three bytes of a
+/// character cut off at the end are left off
+fn decode(encoding: Encoding, bytes: &[u8], partial: bool) -> Option<String> {
+    let most = if partial { bytes.len().min(3) } else { 0 };
+    (0..=most).find_map(|cut| encoding.decode(&bytes[..bytes.len() - cut]))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub encoding: Encoding,
+    /// the language the text reads most like
+    pub language: String,
+    /// the probability that this is the encoding, out of all those the bytes are valid in
+    pub confidence: f64,
+    pub text: String,
+}
+
+/// ranks the encodings some bytes may be in by how plausible the text they decode to is
+#[derive(Debug, Clone)]
+pub struct Detector {
+    models: Vec<LanguageModel>,
+    encodings: Vec<Encoding>,
+}
+
+impl Default for Detector {
+    fn default() -> Self {
+        Self::new(LanguageModel::bundled())
+    }
+}
+
+impl Detector {
+    pub fn new(models: Vec<LanguageModel>) -> Self {
+        Self {
+            models,
+            encodings: Encoding::all(),
+        }
+    }
+
+    /// only consider `encodings`
+    pub fn with_encodings(mut self, encodings: &[Encoding]) -> Self {
+        self.encodings = encodings.to_vec();
+        self
+    }
+
+    /// the best language for `text` and the log likelihood under it
+    fn best_language(&self, text: &str) -> (String, f64) {
+        self.models
+            .iter()
+            .map(|model| (model.name.clone(), model.score(text)))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .unwrap_or_default()
+    }
+
+    /// every encoding `bytes` are valid in, most likely first. a byte order mark decides the
+    /// encoding outright if what follows it is valid. byte patterns that aren't valid in an
+    /// encoding rule it out; the rest are weighed by how likely the encoding is to begin with
+    /// and how likely the text it decodes to is in the language it reads most like
+    pub fn detect(&self, bytes: &[u8]) -> Vec<Candidate> {
+        self.rank(bytes, false)
+    }
+
+    /// like `detect`, for bytes that are only the start of the input, so may end part way
+    /// through a character
+    pub fn detect_prefix(&self, bytes: &[u8]) -> Vec<Candidate> {
+        self.rank(bytes, true)
+    }
+
+    fn rank(&self, bytes: &[u8], partial: bool) -> Vec<Candidate> {
+        if let Some((encoding, len)) = encoding::bom(bytes)
+            && let Some(text) = decode(encoding, &bytes[len..], partial)
+        {
+            let (language, _) = self.best_language(&text);
+            return vec![Candidate {
+                encoding,
+                language,
+                confidence: 1.0,
+                text,
+            }];
+        }
+        let mut scored: Vec<(f64, Candidate)> = self
+            .encodings
+            .iter()
+            .filter_map(|&encoding| {
+                let text = decode(encoding, bytes, partial)?;
+                let (language, score) = self.best_language(&text);
+                let candidate = Candidate {
+                    encoding,
+                    language,
+                    confidence: 0.0,
+                    text,
+                };
+                Some((score + prior(encoding), candidate))
+            })
+            .collect();
+        scored.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        let best = scored.first().map_or(0.0, |&(score, _)| score);
+        let total: f64 = scored.iter().map(|(score, _)| (score - best).exp()).sum();
+        scored
+            .into_iter()
+            .map(|(score, candidate)| Candidate {
+                confidence: (score - best).exp() / total,
+                ..candidate
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::Endianness;
+
+    fn best(detector: &Detector, bytes: &[u8]) -> Candidate {
+        detector.detect(bytes).into_iter().next().unwrap()
+    }
+
+    #[test]
+    fn test_unicode() {
+        let detector = Detector::default();
+        let candidate = best(&detector, "Grüße aus Köln".as_bytes());
+        assert_eq!(candidate.encoding, Encoding::Utf8);
+        assert_eq!(candidate.language, "German");
+        assert!(candidate.confidence > 0.99);
+        let utf16: Vec<u8> = "poëem".encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(
+            best(&detector, &utf16).encoding,
+            Encoding::Utf16(Endianness::BE)
+        );
+        let utf32: Vec<u8> = "ничего"
+            .chars()
+            .flat_map(|c| u32::from(c).to_le_bytes())
+            .collect();
+        let candidate = best(&detector, &utf32);
+        assert_eq!(candidate.encoding, Encoding::Utf32(Endianness::LE));
+        assert_eq!(candidate.text, "ничего");
+        let bom = [0xfe, 0xff, 0x00, 0x69];
+        assert_eq!(detector.detect(&bom).len(), 1);
+    }
+
+    #[test]
+    fn test_legacy() {
+        let detector = Detector::default();
+        let samples = [
+            (
+                "Съешь же ещё этих мягких французских булок",
+                encoding_rs::WINDOWS_1251,
+            ),
+            (
+                "Съешь же ещё этих мягких французских булок",
+                encoding_rs::KOI8_R,
+            ),
+            (
+                "Γαζέες και μυρτιές δεν θα βρω στο χρυσαφί ξέφωτο",
+                encoding_rs::WINDOWS_1253,
+            ),
+            ("Zażółć gęślą jaźń", encoding_rs::WINDOWS_1250),
+            (
+                "Pijamalı hasta yağız şoföre çabucak güvendi",
+                encoding_rs::WINDOWS_1254,
+            ),
+            ("כל בני האדם נולדו בני חורין", encoding_rs::WINDOWS_1255),
+            ("يولد جميع الناس أحرارًا", encoding_rs::WINDOWS_1256),
+            ("吾輩は猫である。名前はまだ無い。", encoding_rs::SHIFT_JIS),
+            ("吾輩は猫である。名前はまだ無い。", encoding_rs::EUC_JP),
+            ("今天天气很好，我们一起去公园散步。", encoding_rs::GB18030),
+            ("今天天氣很好，我們一起去公園散步。", encoding_rs::BIG5),
+        ];
+        for (text, encoding) in samples {
+            let (bytes, _, unmappable) = encoding.encode(text);
+            assert!(!unmappable, "{text}");
+            let candidate = best(&detector, &bytes);
+            assert_eq!(candidate.text, text, "{}", encoding.name());
+            assert_eq!(candidate.encoding, Encoding::Legacy(encoding), "{text}");
+        }
+    }
+
+    #[test]
+    fn test_ranking() {
+        let detector = Detector::default();
+        let candidates = detector.detect(b"yltm\xe4ss\xe4");
+        assert_eq!(candidates[0].text, "yltmässä");
+        assert_eq!(candidates[0].language, "Finnish");
+        let total: f64 = candidates.iter().map(|c| c.confidence).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert!(
+            candidates
+                .windows(2)
+                .all(|pair| pair[0].confidence >= pair[1].confidence)
+        );
+        assert!(candidates.iter().all(|c| c.encoding != Encoding::Utf8));
+        let empty = detector.detect(b"");
+        assert_eq!(empty[0].encoding, Encoding::Utf8);
+        let binary: Vec<u8> = (0..=255).collect();
+        assert!(!detector.detect(&binary).is_empty());
+        let cut = &"Grüße aus Köln".as_bytes()[..14];
+        assert_ne!(detector.detect(cut)[0].encoding, Encoding::Utf8);
+        assert_eq!(detector.detect_prefix(cut)[0].encoding, Encoding::Utf8);
+    }
+}
@@ -0,0 +1,442 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    BE,
+    LE,
+}
+
+/// why some bytes aren't valid in an encoding, and where in them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// UTF-16 with a single byte left over at the end, at `offset`
+    OddLength { offset: usize },
+    /// a UTF-16 high surrogate not followed by a low one, or a low one not after a high one
+    LoneSurrogate { offset: usize, unit: u16 },
+    /// UTF-32 with 1 to 3 bytes left over at the end
+    Incomplete { offset: usize, len: usize },
+    /// a UTF-32 code unit that's a surrogate or beyond U+10FFFF
+    InvalidScalar { offset: usize, unit: u32 },
+    /// bytes that aren't valid UTF-8 or aren't valid in a legacy encoding
+    Malformed { offset: usize, len: usize },
+}
+
+impl DecodeError {
+    pub fn offset(&self) -> usize {
+        match *self {
+            Self::OddLength { offset }
+            | Self::LoneSurrogate { offset, .. }
+            | Self::Incomplete { offset, .. }
+            | Self::InvalidScalar { offset, .. }
+            | Self::Malformed { offset, .. } => offset,
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OddLength { offset } => {
+                write!(f, "offset {offset}: odd byte at the end of UTF-16")
+            }
+            Self::LoneSurrogate { offset, unit } => {
+                write!(f, "offset {offset}: lone surrogate {unit:#06x}")
+            }
+            Self::Incomplete { offset, len } => {
+                write!(
+                    f,
+                    "offset {offset}: {len} bytes left over at the end of UTF-32"
+                )
+            }
+            Self::InvalidScalar { offset, unit } => {
+                write!(f, "offset {offset}: {unit:#x} isn't a Unicode scalar value")
+            }
+            Self::Malformed { offset, len } => {
+                write!(f, "offset {offset}: malformed sequence of {len} bytes")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// `Ok` with the text if nothing had to be replaced, or the first thing that did
+fn strict((text, errors): (String, Vec<DecodeError>)) -> Result<String, DecodeError> {
+    match errors.first() {
+        Some(&error) => Err(error),
+        None => Ok(text),
+    }
+}
+
+/// `bytes` decoded, with U+FFFD for each lone surrogate and for an odd byte at the end
+fn decode_utf16_lossy(bytes: &[u8], endianness: Endianness) -> (String, Vec<DecodeError>) {
+    let units = bytes.chunks_exact(2);
+    let odd = !units.remainder().is_empty();
+    let units = units.map(|chunk| {
+        let unit = [chunk[0], chunk[1]];
+        match endianness {
+            Endianness::BE => u16::from_be_bytes(unit),
+            Endianness::LE => u16::from_le_bytes(unit),
+        }
+    });
+    let mut text = String::with_capacity(bytes.len());
+    let mut errors = Vec::new();
+    let mut offset = 0;
+    for c in char::decode_utf16(units) {
+        match c {
+            Ok(c) => {
+                text.push(c);
+                offset += c.len_utf16() * 2;
+            }
+            Err(error) => {
+                text.push(char::REPLACEMENT_CHARACTER);
+                errors.push(DecodeError::LoneSurrogate {
+                    offset,
+                    unit: error.unpaired_surrogate(),
+                });
+                offset += 2;
+            }
+        }
+    }
+    if odd {
+        text.push(char::REPLACEMENT_CHARACTER);
+        errors.push(DecodeError::OddLength { offset });
+    }
+    (text, errors)
+}
+
+pub fn decode_utf16(bytes: &[u8], endianness: Endianness) -> Result<String, DecodeError> {
+    strict(decode_utf16_lossy(bytes, endianness))
+}
+
+/// `bytes` decoded, with U+FFFD for each code unit that isn't a scalar value and for any bytes
+/// left over at the end
+fn decode_utf32_lossy(bytes: &[u8], endianness: Endianness) -> (String, Vec<DecodeError>) {
+    let units = bytes.chunks_exact(4);
+    let rest = units.remainder().len();
+    let mut text = String::with_capacity(bytes.len());
+    let mut errors = Vec::new();
+    for (i, chunk) in units.enumerate() {
+        let unit = <[u8; 4]>::try_from(chunk).unwrap();
+        let unit = match endianness {
+            Endianness::BE => u32::from_be_bytes(unit),
+            Endianness::LE => u32::from_le_bytes(unit),
+        };
+        text.push(char::from_u32(unit).unwrap_or_else(|| {
+            errors.push(DecodeError::InvalidScalar {
+                offset: 4 * i,
+                unit,
+            });
+            char::REPLACEMENT_CHARACTER
+        }));
+    }
+    if rest > 0 {
+        text.push(char::REPLACEMENT_CHARACTER);
+        errors.push(DecodeError::Incomplete {
+            offset: bytes.len() - rest,
+            len: rest,
+        });
+    }
+    (text, errors)
+}
+
+pub fn decode_utf32(bytes: &[u8], endianness: Endianness) -> Result<String, DecodeError> {
+    strict(decode_utf32_lossy(bytes, endianness))
+}
+
+/// `bytes` decoded by `encoding_rs`, with U+FFFD for each malformed sequence
+fn decode_encoding_rs_lossy(
+    encoding: &'static encoding_rs::Encoding,
+    bytes: &[u8],
+) -> (String, Vec<DecodeError>) {
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let mut text = String::new();
+    let mut errors = Vec::new();
+    let mut read = 0;
+    loop {
+        if let Some(len) = decoder.max_utf8_buffer_length_without_replacement(bytes.len() - read) {
+            text.reserve(len);
+        }
+        let (result, n) =
+            decoder.decode_to_string_without_replacement(&bytes[read..], &mut text, true);
+        read += n;
+        match result {
+            encoding_rs::DecoderResult::InputEmpty => break,
+            encoding_rs::DecoderResult::OutputFull => {}
+            encoding_rs::DecoderResult::Malformed(len, after) => {
+                let len = usize::from(len);
+                errors.push(DecodeError::Malformed {
+                    offset: read - usize::from(after) - len,
+                    len,
+                });
+                text.push(char::REPLACEMENT_CHARACTER);
+            }
+        }
+    }
+    (text, errors)
+}
+
+/// a character encoding: the Unicode ones, decoded here, or a legacy one decoded by `encoding_rs`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16(Endianness),
+    Utf32(Endianness),
+    Legacy(&'static encoding_rs::Encoding),
+}
+
+/// the legacy encodings worth telling apart, the most widely used first
+const LEGACY: &[&encoding_rs::Encoding] = &[
+    encoding_rs::WINDOWS_1252,
+    encoding_rs::ISO_8859_15,
+    encoding_rs::WINDOWS_1250,
+    encoding_rs::ISO_8859_2,
+    encoding_rs::WINDOWS_1251,
+    encoding_rs::KOI8_R,
+    encoding_rs::KOI8_U,
+    encoding_rs::ISO_8859_5,
+    encoding_rs::WINDOWS_1253,
+    encoding_rs::ISO_8859_7,
+    encoding_rs::WINDOWS_1254,
+    encoding_rs::WINDOWS_1255,
+    encoding_rs::ISO_8859_8,
+    encoding_rs::WINDOWS_1256,
+    encoding_rs::ISO_8859_6,
+    encoding_rs::WINDOWS_1257,
+    encoding_rs::ISO_8859_13,
+    encoding_rs::ISO_8859_4,
+    encoding_rs::WINDOWS_1258,
+    encoding_rs::ISO_8859_3,
+    encoding_rs::ISO_8859_10,
+    encoding_rs::ISO_8859_14,
+    encoding_rs::ISO_8859_16,
+    encoding_rs::SHIFT_JIS,
+    encoding_rs::EUC_JP,
+    encoding_rs::GB18030,
+    encoding_rs::BIG5,
+];
+
+impl Encoding {
+    /// every encoding that can be detected, the most widely used first
+    pub fn all() -> Vec<Self> {
+        let mut all = vec![
+            Self::Utf8,
+            Self::Utf16(Endianness::LE),
+            Self::Utf16(Endianness::BE),
+            Self::Utf32(Endianness::LE),
+            Self::Utf32(Endianness::BE),
+        ];
+        all.extend(LEGACY.iter().map(|&encoding| Self::Legacy(encoding)));
+        all
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf16(Endianness::LE) => "UTF-16LE",
+            Self::Utf16(Endianness::BE) => "UTF-16BE",
+            Self::Utf32(Endianness::LE) => "UTF-32LE",
+            Self::Utf32(Endianness::BE) => "UTF-32BE",
+            Self::Legacy(encoding) => encoding.name(),
+        }
+    }
+
+    /// the encoding with a WHATWG label such as `latin1` or `sjis`, or a name such as `UTF-32BE`
+    pub fn for_label(label: &str) -> Option<Self> {
+        let unicode = Self::all()
+            .into_iter()
+            .take(5)
+            .find(|encoding| encoding.name().eq_ignore_ascii_case(label));
+        unicode.or_else(
+            || match encoding_rs::Encoding::for_label(label.as_bytes())? {
+                encoding if encoding == encoding_rs::UTF_8 => Some(Self::Utf8),
+                encoding if encoding == encoding_rs::UTF_16LE => Some(Self::Utf16(Endianness::LE)),
+                encoding if encoding == encoding_rs::UTF_16BE => Some(Self::Utf16(Endianness::BE)),
+                encoding => Some(Self::Legacy(encoding)),
+            },
+        )
+    }
+
+    /// the byte order mark text in this encoding may start with; legacy encodings have none
+    pub fn byte_order_mark(&self) -> &'static [u8] {
+        match self {
+            Self::Utf8 => &[0xef, 0xbb, 0xbf],
+            Self::Utf16(Endianness::BE) => &[0xfe, 0xff],
+            Self::Utf16(Endianness::LE) => &[0xff, 0xfe],
+            Self::Utf32(Endianness::BE) => &[0x00, 0x00, 0xfe, 0xff],
+            Self::Utf32(Endianness::LE) => &[0xff, 0xfe, 0x00, 0x00],
+            Self::Legacy(_) => &[],
+        }
+    }
+
+    /// `bytes` decoded, or `None` if they aren't valid in this encoding
+    pub fn decode(&self, bytes: &[u8]) -> Option<String> {
+        match self {
+            Self::Utf8 => std::str::from_utf8(bytes).ok().map(ToOwned::to_owned),
+            Self::Legacy(encoding) => encoding
+                .decode_without_bom_handling_and_without_replacement(bytes)
+                .map(|text| text.into_owned()),
+            _ => self.try_decode(bytes).ok(),
+        }
+    }
+
+    /// `bytes` decoded, or why they aren't valid in this encoding
+    pub fn try_decode(&self, bytes: &[u8]) -> Result<String, DecodeError> {
+        strict(self.decode_lossy(bytes))
+    }
+
+    /// `bytes` decoded with U+FFFD in place of whatever isn't valid in this encoding, and the
+    /// errors that were replaced, in order
+    pub fn decode_lossy(&self, bytes: &[u8]) -> (String, Vec<DecodeError>) {
+        match self {
+            Self::Utf8 => decode_encoding_rs_lossy(encoding_rs::UTF_8, bytes),
+            Self::Utf16(endianness) => decode_utf16_lossy(bytes, *endianness),
+            Self::Utf32(endianness) => decode_utf32_lossy(bytes, *endianness),
+            Self::Legacy(encoding) => decode_encoding_rs_lossy(encoding, bytes),
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// the encoding `bytes` declares with a byte order mark, and the length of the mark
+pub fn bom(bytes: &[u8]) -> Option<(Encoding, usize)> {
+    // UTF-32LE's mark starts with UTF-16LE's, so is looked for first
+    [
+        Encoding::Utf32(Endianness::LE),
+        Encoding::Utf32(Endianness::BE),
+        Encoding::Utf16(Endianness::LE),
+        Encoding::Utf16(Endianness::BE),
+        Encoding::Utf8,
+    ]
+    .into_iter()
+    .find(|encoding| bytes.starts_with(encoding.byte_order_mark()))
+    .map(|encoding| (encoding, encoding.byte_order_mark().len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let utf16 = [0x00, 0x70, 0x00, 0xeb];
+        assert_eq!(
+            Encoding::Utf16(Endianness::BE).decode(&utf16).as_deref(),
+            Some("pë")
+        );
+        assert_eq!(Encoding::Utf16(Endianness::BE).decode(&utf16[1..]), None);
+        assert_eq!(
+            Encoding::Utf32(Endianness::LE).decode(&[0x3c, 0xd8, 0x00, 0x00]),
+            None
+        );
+        assert_eq!(
+            Encoding::Utf32(Endianness::LE)
+                .decode(&[0x00, 0xf6, 0x01, 0x00])
+                .as_deref(),
+            Some("😀")
+        );
+        let koi8 = Encoding::for_label("koi8-r").unwrap();
+        assert_eq!(koi8.decode(&[0xcd, 0xc9, 0xd2]).as_deref(), Some("мир"));
+        assert_eq!(Encoding::Utf8.decode(&[0xc3]), None);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let utf16le = Encoding::Utf16(Endianness::LE);
+        assert_eq!(
+            utf16le.try_decode(&[0x70, 0x00, 0xeb]),
+            Err(DecodeError::OddLength { offset: 2 })
+        );
+        let lone = [0x70, 0x00, 0x3d, 0xd8, 0x70, 0x00, 0x00, 0xde];
+        assert_eq!(
+            utf16le.try_decode(&lone),
+            Err(DecodeError::LoneSurrogate {
+                offset: 2,
+                unit: 0xd83d
+            })
+        );
+        assert_eq!(
+            utf16le.decode_lossy(&lone),
+            (
+                "p\u{fffd}p\u{fffd}".to_owned(),
+                vec![
+                    DecodeError::LoneSurrogate {
+                        offset: 2,
+                        unit: 0xd83d
+                    },
+                    DecodeError::LoneSurrogate {
+                        offset: 6,
+                        unit: 0xde00
+                    }
+                ]
+            )
+        );
+        assert_eq!(
+            utf16le.try_decode(&[0x3d, 0xd8, 0x00, 0xde]).as_deref(),
+            Ok("😀")
+        );
+        let utf32be = Encoding::Utf32(Endianness::BE);
+        let (text, errors) = utf32be.decode_lossy(&[0, 0, 0, 0x69, 0, 0x11, 0, 0, 0, 0]);
+        assert_eq!(text, "i\u{fffd}\u{fffd}");
+        assert_eq!(
+            errors,
+            [
+                DecodeError::InvalidScalar {
+                    offset: 4,
+                    unit: 0x110000
+                },
+                DecodeError::Incomplete { offset: 8, len: 2 }
+            ]
+        );
+        let (text, errors) = Encoding::Utf8.decode_lossy(b"caf\xc3(\xe9");
+        assert_eq!(text, "caf\u{fffd}(\u{fffd}");
+        assert_eq!(errors[1], DecodeError::Malformed { offset: 5, len: 1 });
+        assert_eq!(errors[0].offset(), 3);
+        let shift_jis = Encoding::Legacy(encoding_rs::SHIFT_JIS);
+        assert_eq!(
+            shift_jis.try_decode(b"\x82\xa0\x82"),
+            Err(DecodeError::Malformed { offset: 2, len: 1 })
+        );
+    }
+
+    #[test]
+    fn test_bom() {
+        assert_eq!(
+            bom(&[0xff, 0xfe, 0x00, 0x00, 0x69, 0x00, 0x00, 0x00]),
+            Some((Encoding::Utf32(Endianness::LE), 4))
+        );
+        assert_eq!(
+            bom(&[0x00, 0x00, 0xfe, 0xff]),
+            Some((Encoding::Utf32(Endianness::BE), 4))
+        );
+        assert_eq!(
+            bom(&[0xff, 0xfe, 0x69, 0x00]),
+            Some((Encoding::Utf16(Endianness::LE), 2))
+        );
+        assert_eq!(bom(&[0xef, 0xbb, 0xbf]), Some((Encoding::Utf8, 3)));
+        assert_eq!(bom(b"plain"), None);
+    }
+
+    #[test]
+    fn test_for_label() {
+        assert_eq!(
+            Encoding::for_label("latin1"),
+            Some(Encoding::Legacy(encoding_rs::WINDOWS_1252))
+        );
+        assert_eq!(
+            Encoding::for_label("utf-32be"),
+            Some(Encoding::Utf32(Endianness::BE))
+        );
+        assert_eq!(
+            Encoding::for_label("utf-16"),
+            Some(Encoding::Utf16(Endianness::LE))
+        );
+        assert_eq!(Encoding::for_label("ebcdic"), None);
+        assert_eq!(Encoding::Utf8.to_string(), "UTF-8");
+    }
+}
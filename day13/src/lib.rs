@@ -0,0 +1,3 @@
+pub mod detect;
+pub mod encoding;
+pub mod transcode;
@@ -1,67 +1,34 @@
-use std::fs;
+use crossword::solver::{Comparison, Crossword, Solver};
+use day13::detect::Detector;
+use day13::encoding::{Encoding, Endianness};
+use day13::transcode::{self, Mode, Transcoder};
+use std::io::{self, Read};
+use std::{env, fs, process};
 
-type FromUtf16Result = Result<String, std::string::FromUtf16Error>;
-const MAX_LATIN_CODE: u16 = 0x1eff; // End of "Latin Extended Additional" block
-
-enum Endianness {
-    BE,
-    LE,
+/// the most likely decoding of `bytes`
+fn decode(detector: &Detector, bytes: &[u8]) -> String {
+    detector
+        .detect(bytes)
+        .into_iter()
+        .next()
+        .map(|candidate| candidate.text)
+        .unwrap_or_default()
 }
 
-fn decode_utf16(bytes: &[u8], endianness: &Endianness) -> FromUtf16Result {
-    let u16s: Vec<u16> = bytes
-        .chunks_exact(2)
-        .map(|chunk| {
-            let (hi, lo) = match endianness {
-                Endianness::BE => (0, 1),
-                Endianness::LE => (1, 0),
-            };
-            u16::from(chunk[hi]) * 256 + u16::from(chunk[lo])
-        })
-        .collect();
-    String::from_utf16(&u16s)
-}
-
-fn decode(bytes: &[u8]) -> String {
-    if bytes[..2] == [0xfe, 0xff] {
-        return decode_utf16(&bytes[2..], &Endianness::BE).unwrap();
-    } else if bytes[..2] == [0xff, 0xfe] {
-        return decode_utf16(&bytes[2..], &Endianness::LE).unwrap();
-    } else if bytes[..3] == [0xef, 0xbb, 0xbf] {
-        return std::str::from_utf8(&bytes[3..]).unwrap().to_owned();
-    }
-    if let Ok(utf8) = String::from_utf8(bytes.to_owned()) {
-        return utf8;
-    }
-    for endianness in [Endianness::BE, Endianness::LE] {
-        if let Ok(utf16) = decode_utf16(bytes, &endianness) {
-            if utf16
-                .chars()
-                .all(|c| c.is_alphabetic() && (c as u16) <= MAX_LATIN_CODE)
-            {
-                return utf16;
-            }
-        }
-    }
-    let latin1: String = bytes.iter().map(|&b| char::from(b)).collect();
-    assert!(latin1.chars().all(char::is_alphabetic));
-    latin1
-}
-
-fn find_match(blank: &str, words: &[String]) -> Option<usize> {
-    let length = blank.chars().count();
-    let index = blank.chars().position(|c| c != '.').unwrap();
-    let letter = blank.chars().nth(index);
-    for (i, word) in words.iter().enumerate() {
-        if word.chars().count() == length && word.chars().nth(index) == letter {
-            return Some(i + 1);
-        }
-    }
-    None
+/// the encodings the puzzle's words are in. detecting among only these keeps Latin-1 words from
+/// reading better in some other legacy encoding
+fn puzzle_detector() -> Detector {
+    Detector::default().with_encodings(&[
+        Encoding::Utf8,
+        Encoding::Utf16(Endianness::LE),
+        Encoding::Utf16(Endianness::BE),
+        Encoding::Legacy(encoding_rs::WINDOWS_1252),
+    ])
 }
 
 fn solution(input: &str) -> usize {
     let (dictionary, crossword) = input.split_once("\n\n").unwrap();
+    let detector = puzzle_detector();
     let words: Vec<String> = dictionary
         .lines()
         .map(|line| {
@@ -69,18 +36,74 @@ fn solution(input: &str) -> usize {
                 .step_by(2)
                 .map(|i| u8::from_str_radix(&line[i..i + 2], 16).unwrap())
                 .collect();
-            decode(&bytes)
+            decode(&detector, &bytes)
         })
         .collect();
-    let crossword: Vec<&str> = crossword.lines().map(str::trim).collect();
-    let mut sum = 0;
-    for blank in &crossword {
-        sum += find_match(blank, &words).unwrap();
+    let crossword = Crossword::from_lines(crossword);
+    let solution = Solver::new(&words, Comparison::Exact)
+        .solve(&crossword)
+        .unwrap();
+    solution.words.iter().map(|word| word.unwrap() + 1).sum()
+}
+
+const TRANSCODE_USAGE: &str =
+    "usage: day13 transcode [--from ENCODING] [--to ENCODING] [--bom] [--strict] [FILE]";
+
+/// `transcode [--from ENCODING] [--to ENCODING] [--bom] [--strict] [FILE]` converts FILE, or
+/// stdin, to UTF-8 or the `--to` encoding on stdout, detecting the source encoding unless it's
+/// given. what can't be converted is reported on stderr, and is an error with `--strict`
+fn transcode(args: &[String]) {
+    let encoding = |label: Option<&String>| {
+        let label = label.expect(TRANSCODE_USAGE);
+        Encoding::for_label(label).unwrap_or_else(|| panic!("unknown encoding {label}"))
+    };
+    let (mut from, mut to, mut bom, mut mode, mut path) =
+        (None, Encoding::Utf8, false, Mode::Replace, None);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" => from = Some(encoding(args.next())),
+            "--to" => to = encoding(args.next()),
+            "--bom" => bom = true,
+            "--strict" => mode = Mode::Strict,
+            _ if arg.starts_with("--") || path.is_some() => panic!("{TRANSCODE_USAGE}"),
+            _ => path = Some(arg),
+        }
+    }
+    let input: Box<dyn Read> = match path {
+        Some(path) => Box::new(fs::File::open(path).unwrap()),
+        None => Box::new(io::stdin().lock()),
+    };
+    let (from, input): (Encoding, Box<dyn Read>) = match from {
+        Some(from) => (from, input),
+        None => {
+            let (candidate, input) = transcode::detect(&Detector::default(), input).unwrap();
+            let candidate = candidate.expect("couldn't detect the encoding");
+            eprintln!(
+                "detected {} ({}, {:.0}%)",
+                candidate.encoding,
+                candidate.language,
+                candidate.confidence * 100.0
+            );
+            (candidate.encoding, Box::new(input))
+        }
+    };
+    let mut transcoder = Transcoder::new(from, to).with_mode(mode);
+    if bom {
+        transcoder = transcoder.with_bom();
+    }
+    let result = transcoder.transcode(input, io::stdout().lock(), |problem| eprintln!("{problem}"));
+    if let Err(error) = result {
+        eprintln!("{error}");
+        process::exit(1);
     }
-    sum
 }
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("transcode") {
+        return transcode(&args[1..]);
+    }
     let input = fs::read_to_string("input").unwrap();
     let answer = solution(&input);
     println!("{answer}");
@@ -95,4 +118,13 @@ mod tests {
         let input = fs::read_to_string("test-input").unwrap();
         assert_eq!(solution(&input), 47);
     }
+
+    #[test]
+    fn test_latin1_words() {
+        let detector = puzzle_detector();
+        for word in ["smørrebrød", "naïve", "ýmis", "þú", "Ångström", "façade"] {
+            let (bytes, _, _) = encoding_rs::WINDOWS_1252.encode(word);
+            assert_eq!(decode(&detector, &bytes), word);
+        }
+    }
 }
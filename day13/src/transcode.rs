@@ -0,0 +1,636 @@
+use crate::detect::{Candidate, Detector};
+use crate::encoding::{Encoding, Endianness};
+use encoding_rs::{DecoderResult, EncoderResult};
+use std::char::REPLACEMENT_CHARACTER;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+/// how many bytes are read and converted at a time
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// how many bytes at the start of the input the source encoding is detected from
+pub const SAMPLE_SIZE: usize = 8 * 1024;
+
+/// what an unmappable character is replaced with in a legacy encoding
+const SUBSTITUTE: u8 = b'?';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// stop at the first byte sequence that can't be decoded or character that can't be encoded
+    Strict,
+    /// decode what can't be decoded to U+FFFD, encode what can't be encoded as `?`, and go on
+    Replace,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Problem {
+    /// `len` bytes at `offset` in the input aren't valid in the source encoding
+    Malformed { offset: u64, len: usize },
+    /// the character decoded from the bytes at `offset` has no mapping in the target encoding
+    Unmappable { offset: u64, c: char },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed { offset, len } => {
+                write!(f, "offset {offset}: malformed sequence of {len} bytes")
+            }
+            Self::Unmappable { offset, c } => {
+                write!(
+                    f,
+                    "offset {offset}: unmappable character {c:?} (U+{:04X})",
+                    *c as u32
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum TranscodeError {
+    Io(io::Error),
+    /// the first problem met in strict mode
+    Problem(Problem),
+}
+
+impl fmt::Display for TranscodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::Problem(problem) => write!(f, "{problem}"),
+        }
+    }
+}
+
+impl std::error::Error for TranscodeError {}
+
+impl From<io::Error> for TranscodeError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// how many bytes `c` takes up in `encoding`
+fn source_len(encoding: Encoding, c: char) -> u64 {
+    let len = match encoding {
+        Encoding::Utf8 => c.len_utf8(),
+        Encoding::Utf16(_) => c.len_utf16() * 2,
+        Encoding::Utf32(_) => 4,
+        Encoding::Legacy(encoding) if encoding.is_single_byte() => 1,
+        Encoding::Legacy(encoding) => {
+            let mut bytes = [0; 8];
+            let (_, _, written) = encoding.new_encoder().encode_from_utf8_without_replacement(
+                c.encode_utf8(&mut [0; 4]),
+                &mut bytes,
+                true,
+            );
+            written
+        }
+    };
+    len as u64
+}
+
+/// fills as much of `buffer` as `reader` has left, returning how much that was
+fn read_chunk(reader: &mut impl Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buffer.len() {
+        match reader.read(&mut buffer[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(len)
+}
+
+/// a byte sequence decoded to U+FFFD
+struct Malformed {
+    /// where in the decoded text the U+FFFD is
+    index: usize,
+    offset: u64,
+    len: usize,
+}
+
+/// decodes input that arrives in chunks, which may split characters
+enum Decoder {
+    Utf32 {
+        endianness: Endianness,
+        pending: Vec<u8>,
+    },
+    Other(encoding_rs::Decoder),
+}
+
+impl Decoder {
+    fn new(encoding: Encoding) -> Self {
+        let encoding = match encoding {
+            Encoding::Utf8 => encoding_rs::UTF_8,
+            Encoding::Utf16(Endianness::BE) => encoding_rs::UTF_16BE,
+            Encoding::Utf16(Endianness::LE) => encoding_rs::UTF_16LE,
+            Encoding::Utf32(endianness) => {
+                return Self::Utf32 {
+                    endianness,
+                    pending: Vec::new(),
+                };
+            }
+            Encoding::Legacy(encoding) => encoding,
+        };
+        Self::Other(encoding.new_decoder_without_bom_handling())
+    }
+
+    /// decodes `bytes`, which start `offset` bytes into the input, onto the end of `text`, with
+    /// U+FFFD for each malformed sequence. `last` flushes a character left incomplete
+    fn decode(
+        &mut self,
+        bytes: &[u8],
+        offset: u64,
+        last: bool,
+        text: &mut String,
+    ) -> Vec<Malformed> {
+        let mut malformed = Vec::new();
+        match self {
+            Self::Utf32 {
+                endianness,
+                pending,
+            } => {
+                let start = offset - pending.len() as u64;
+                pending.extend_from_slice(bytes);
+                let units = pending.chunks_exact(4);
+                let rest = units.remainder().len();
+                for (i, unit) in units.enumerate() {
+                    let unit = <[u8; 4]>::try_from(unit).unwrap();
+                    let unit = match endianness {
+                        Endianness::BE => u32::from_be_bytes(unit),
+                        Endianness::LE => u32::from_le_bytes(unit),
+                    };
+                    let c = char::from_u32(unit).unwrap_or_else(|| {
+                        malformed.push(Malformed {
+                            index: text.len(),
+                            offset: start + 4 * i as u64,
+                            len: 4,
+                        });
+                        REPLACEMENT_CHARACTER
+                    });
+                    text.push(c);
+                }
+                pending.drain(..pending.len() - rest);
+                if last && !pending.is_empty() {
+                    malformed.push(Malformed {
+                        index: text.len(),
+                        offset: offset + bytes.len() as u64 - rest as u64,
+                        len: rest,
+                    });
+                    text.push(REPLACEMENT_CHARACTER);
+                    pending.clear();
+                }
+            }
+            Self::Other(decoder) => {
+                let mut read = 0;
+                loop {
+                    if let Some(len) =
+                        decoder.max_utf8_buffer_length_without_replacement(bytes.len() - read)
+                    {
+                        text.reserve(len);
+                    }
+                    let (result, n) =
+                        decoder.decode_to_string_without_replacement(&bytes[read..], text, last);
+                    read += n;
+                    match result {
+                        DecoderResult::InputEmpty => break,
+                        DecoderResult::OutputFull => {}
+                        DecoderResult::Malformed(len, after) => {
+                            let end = offset + read as u64 - u64::from(after);
+                            malformed.push(Malformed {
+                                index: text.len(),
+                                offset: end - u64::from(len),
+                                len: len.into(),
+                            });
+                            text.push(REPLACEMENT_CHARACTER);
+                        }
+                    }
+                }
+            }
+        }
+        malformed
+    }
+}
+
+enum Encoder {
+    Utf8,
+    Utf16(Endianness),
+    Utf32(Endianness),
+    Legacy(encoding_rs::Encoder),
+}
+
+impl Encoder {
+    fn new(encoding: Encoding) -> Self {
+        match encoding {
+            Encoding::Utf8 => Self::Utf8,
+            Encoding::Utf16(endianness) => Self::Utf16(endianness),
+            Encoding::Utf32(endianness) => Self::Utf32(endianness),
+            Encoding::Legacy(encoding) => Self::Legacy(encoding.new_encoder()),
+        }
+    }
+
+    /// encodes as much of `text` as can be onto the end of `bytes`, stopping at the first
+    /// character that can't be encoded, which is returned with its index
+    fn encode(&mut self, text: &str, last: bool, bytes: &mut Vec<u8>) -> Option<(usize, char)> {
+        match self {
+            Self::Utf8 => bytes.extend_from_slice(text.as_bytes()),
+            Self::Utf16(Endianness::BE) => {
+                bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes))
+            }
+            Self::Utf16(Endianness::LE) => {
+                bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes))
+            }
+            Self::Utf32(Endianness::BE) => {
+                bytes.extend(text.chars().flat_map(|c| u32::from(c).to_be_bytes()))
+            }
+            Self::Utf32(Endianness::LE) => {
+                bytes.extend(text.chars().flat_map(|c| u32::from(c).to_le_bytes()))
+            }
+            Self::Legacy(encoder) => {
+                let mut read = 0;
+                loop {
+                    if let Some(len) =
+                        encoder.max_buffer_length_from_utf8_without_replacement(text.len() - read)
+                    {
+                        bytes.reserve(len);
+                    }
+                    let (result, n) = encoder.encode_from_utf8_to_vec_without_replacement(
+                        &text[read..],
+                        bytes,
+                        last,
+                    );
+                    read += n;
+                    match result {
+                        EncoderResult::InputEmpty => break,
+                        EncoderResult::OutputFull => {}
+                        EncoderResult::Unmappable(c) => return Some((read - c.len_utf8(), c)),
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// where in the input the decoded text came from: the offsets of the characters at some of its
+/// indices, from which the offsets of those after them follow
+struct Origins {
+    encoding: Encoding,
+    anchors: Vec<(usize, u64)>,
+}
+
+impl Origins {
+    fn new(encoding: Encoding, offset: u64, malformed: &[Malformed]) -> Self {
+        let mut anchors = vec![(0, offset)];
+        for malformed in malformed {
+            anchors.push((malformed.index, malformed.offset));
+            anchors.push((
+                malformed.index + REPLACEMENT_CHARACTER.len_utf8(),
+                malformed.offset + malformed.len as u64,
+            ));
+        }
+        Self { encoding, anchors }
+    }
+
+    /// the offset in the input of the character at `index` in `text`
+    fn offset(&self, text: &str, index: usize) -> u64 {
+        let &(start, offset) = self
+            .anchors
+            .iter()
+            .rev()
+            .find(|&&(start, _)| start <= index)
+            .unwrap();
+        let len: u64 = text[start..index]
+            .chars()
+            .map(|c| source_len(self.encoding, c))
+            .sum();
+        offset + len
+    }
+}
+
+/// converts text from one encoding to another a chunk at a time, so input of any size takes
+/// the same memory
+#[derive(Debug, Clone)]
+pub struct Transcoder {
+    from: Encoding,
+    to: Encoding,
+    mode: Mode,
+    bom: bool,
+    chunk_size: usize,
+}
+
+impl Transcoder {
+    pub fn new(from: Encoding, to: Encoding) -> Self {
+        Self {
+            from,
+            to,
+            mode: Mode::Replace,
+            bom: false,
+            chunk_size: CHUNK_SIZE,
+        }
+    }
+
+    pub fn with_mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// start the output with a byte order mark, if the target is a Unicode encoding
+    pub fn with_bom(mut self) -> Self {
+        self.bom = true;
+        self
+    }
+
+    /// read `chunk_size` bytes at a time, at least enough for any byte order mark
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(4);
+        self
+    }
+
+    /// converts everything `reader` yields and writes it to `writer`. a byte order mark of the
+    /// source encoding at the start is dropped. in replace mode each problem is passed to
+    /// `report` as it's met; in strict mode the first is an error, once everything before it
+    /// has been written
+    pub fn transcode(
+        &self,
+        mut reader: impl Read,
+        mut writer: impl Write,
+        mut report: impl FnMut(Problem),
+    ) -> Result<(), TranscodeError> {
+        let mut decoder = Decoder::new(self.from);
+        let mut encoder = Encoder::new(self.to);
+        let mut buffer = vec![0; self.chunk_size];
+        let mut text = String::new();
+        let mut bytes = Vec::new();
+        if self.bom {
+            bytes.extend_from_slice(self.to.byte_order_mark());
+        }
+        let mut len = read_chunk(&mut reader, &mut buffer)?;
+        let mut start = if buffer[..len].starts_with(self.from.byte_order_mark()) {
+            self.from.byte_order_mark().len()
+        } else {
+            0
+        };
+        // where in the input the next chunk starts, and the next character decoded from it
+        let mut offset = 0;
+        let mut position = start as u64;
+        loop {
+            let last = len == 0;
+            text.clear();
+            let mut malformed =
+                decoder.decode(&buffer[start..len], offset + start as u64, last, &mut text);
+            let origins = Origins::new(self.from, position, &malformed);
+            if self.mode == Mode::Strict && !malformed.is_empty() {
+                let first = malformed.swap_remove(0);
+                text.truncate(first.index);
+                malformed.clear();
+                self.encode(&mut encoder, &text, last, &origins, &mut bytes, &mut report)?;
+                writer.write_all(&bytes)?;
+                return Err(TranscodeError::Problem(Problem::Malformed {
+                    offset: first.offset,
+                    len: first.len,
+                }));
+            }
+            let mut problems: Vec<Problem> = malformed
+                .iter()
+                .map(|malformed| Problem::Malformed {
+                    offset: malformed.offset,
+                    len: malformed.len,
+                })
+                .collect();
+            let encoded = self.encode(
+                &mut encoder,
+                &text,
+                last,
+                &origins,
+                &mut bytes,
+                &mut |problem| problems.push(problem),
+            );
+            writer.write_all(&bytes)?;
+            bytes.clear();
+            problems.sort_by_key(|problem| match problem {
+                Problem::Malformed { offset, .. } | Problem::Unmappable { offset, .. } => *offset,
+            });
+            problems.into_iter().for_each(&mut report);
+            encoded?;
+            if last {
+                break;
+            }
+            position = origins.offset(&text, text.len());
+            offset += len as u64;
+            start = 0;
+            len = read_chunk(&mut reader, &mut buffer)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// encodes `text` onto the end of `bytes`, substituting for each character that can't be
+    /// encoded, or in strict mode stopping at the first
+    fn encode(
+        &self,
+        encoder: &mut Encoder,
+        text: &str,
+        last: bool,
+        origins: &Origins,
+        bytes: &mut Vec<u8>,
+        report: &mut impl FnMut(Problem),
+    ) -> Result<(), TranscodeError> {
+        let mut read = 0;
+        while let Some((index, c)) = encoder.encode(&text[read..], last, bytes) {
+            let index = read + index;
+            let problem = Problem::Unmappable {
+                offset: origins.offset(text, index),
+                c,
+            };
+            if self.mode == Mode::Strict {
+                return Err(TranscodeError::Problem(problem));
+            }
+            report(problem);
+            bytes.push(SUBSTITUTE);
+            read = index + c.len_utf8();
+        }
+        Ok(())
+    }
+}
+
+/// detects the encoding of the start of `reader`, returning the most likely candidate with a
+/// reader that still yields everything, the start included
+pub fn detect<R: Read>(
+    detector: &Detector,
+    mut reader: R,
+) -> io::Result<(Option<Candidate>, impl Read + use<R>)> {
+    let mut sample = vec![0; SAMPLE_SIZE];
+    let len = read_chunk(&mut reader, &mut sample)?;
+    sample.truncate(len);
+    let candidate = if len < SAMPLE_SIZE {
+        detector.detect(&sample)
+    } else {
+        detector.detect_prefix(&sample)
+    }
+    .into_iter()
+    .next();
+    Ok((candidate, io::Cursor::new(sample).chain(reader)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcode(
+        transcoder: &Transcoder,
+        input: &[u8],
+    ) -> (Result<Vec<u8>, Problem>, Vec<Problem>) {
+        let mut output = Vec::new();
+        let mut problems = Vec::new();
+        let result = transcoder.transcode(input, &mut output, |problem| problems.push(problem));
+        match result {
+            Ok(()) => (Ok(output), problems),
+            Err(TranscodeError::Problem(problem)) => (Err(problem), problems),
+            Err(TranscodeError::Io(error)) => panic!("{error}"),
+        }
+    }
+
+    #[test]
+    fn test_transcode() {
+        let text = "Съешь же ещё этих мягких французских булок 😀";
+        let utf16: Vec<u8> = [0xfe, 0xff]
+            .into_iter()
+            .chain(text.encode_utf16().flat_map(u16::to_be_bytes))
+            .collect();
+        let utf32: Vec<u8> = text
+            .chars()
+            .flat_map(|c| u32::from(c).to_le_bytes())
+            .collect();
+        let (koi8, _, _) = encoding_rs::KOI8_R.encode("Съешь же ещё");
+        let (sjis, _, _) = encoding_rs::SHIFT_JIS.encode("吾輩は猫である");
+        let utf16be = Encoding::Utf16(Endianness::BE);
+        let utf32le = Encoding::Utf32(Endianness::LE);
+        let legacy = Encoding::Legacy;
+        let cases: [(Encoding, &[u8], Encoding, &[u8]); 5] = [
+            (Encoding::Utf8, text.as_bytes(), utf16be, &utf16[2..]),
+            (utf16be, &utf16, Encoding::Utf8, text.as_bytes()),
+            (utf32le, &utf32, utf16be, &utf16[2..]),
+            (
+                legacy(encoding_rs::KOI8_R),
+                &koi8,
+                legacy(encoding_rs::WINDOWS_1251),
+                &encoding_rs::WINDOWS_1251.encode("Съешь же ещё").0,
+            ),
+            (
+                legacy(encoding_rs::SHIFT_JIS),
+                &sjis,
+                legacy(encoding_rs::EUC_JP),
+                &encoding_rs::EUC_JP.encode("吾輩は猫である").0,
+            ),
+        ];
+        for (from, input, to, expected) in cases {
+            for chunk_size in [4, 5, 7, CHUNK_SIZE] {
+                let transcoder = Transcoder::new(from, to).with_chunk_size(chunk_size);
+                let (output, problems) = transcode(&transcoder, input);
+                assert_eq!(
+                    output.as_deref(),
+                    Ok(expected),
+                    "{from} to {to} by {chunk_size}"
+                );
+                assert!(problems.is_empty());
+            }
+        }
+        let transcoder = Transcoder::new(Encoding::Utf8, utf16be).with_bom();
+        assert_eq!(transcode(&transcoder, text.as_bytes()).0, Ok(utf16));
+        let transcoder =
+            Transcoder::new(Encoding::Utf8, legacy(encoding_rs::WINDOWS_1252)).with_bom();
+        assert_eq!(
+            transcode(&transcoder, "\u{feff}é".as_bytes()).0,
+            Ok(vec![0xe9])
+        );
+    }
+
+    #[test]
+    fn test_problems() {
+        let latin1 = Encoding::Legacy(encoding_rs::WINDOWS_1252);
+        let input = "naïve ж café\u{2603}".as_bytes();
+        for chunk_size in [4, 5, CHUNK_SIZE] {
+            let transcoder = Transcoder::new(Encoding::Utf8, latin1).with_chunk_size(chunk_size);
+            let (output, problems) = transcode(&transcoder, input);
+            assert_eq!(output, Ok(b"na\xefve ? caf\xe9?".to_vec()));
+            assert_eq!(
+                problems,
+                [
+                    Problem::Unmappable { offset: 7, c: 'ж' },
+                    Problem::Unmappable {
+                        offset: 15,
+                        c: '\u{2603}'
+                    }
+                ]
+            );
+            let strict = transcoder.with_mode(Mode::Strict);
+            let (output, problems) = transcode(&strict, input);
+            assert_eq!(output, Err(Problem::Unmappable { offset: 7, c: 'ж' }));
+            assert!(problems.is_empty());
+        }
+        let input = b"ab\xc3(d\xe2\x82";
+        for chunk_size in [4, 5, CHUNK_SIZE] {
+            let transcoder =
+                Transcoder::new(Encoding::Utf8, Encoding::Utf8).with_chunk_size(chunk_size);
+            let (output, problems) = transcode(&transcoder, input);
+            assert_eq!(output, Ok("ab\u{fffd}(d\u{fffd}".as_bytes().to_vec()));
+            assert_eq!(
+                problems,
+                [
+                    Problem::Malformed { offset: 2, len: 1 },
+                    Problem::Malformed { offset: 5, len: 2 }
+                ]
+            );
+        }
+        let utf32 = Transcoder::new(Encoding::Utf32(Endianness::BE), latin1).with_chunk_size(6);
+        let (output, problems) = transcode(&utf32, b"\0\0\0a\0\0\xd8\0\0\0\x046\0\0");
+        assert_eq!(output, Ok(b"a???".to_vec()));
+        assert_eq!(
+            problems,
+            [
+                Problem::Malformed { offset: 4, len: 4 },
+                Problem::Unmappable {
+                    offset: 4,
+                    c: '\u{fffd}'
+                },
+                Problem::Unmappable { offset: 8, c: 'ж' },
+                Problem::Malformed { offset: 12, len: 2 },
+                Problem::Unmappable {
+                    offset: 12,
+                    c: '\u{fffd}'
+                },
+            ]
+        );
+        let strict = Transcoder::new(Encoding::Utf8, Encoding::Utf8).with_mode(Mode::Strict);
+        let mut output = Vec::new();
+        let result = strict.transcode(&b"abc\xffdef"[..], &mut output, |_| {});
+        assert!(matches!(
+            result,
+            Err(TranscodeError::Problem(Problem::Malformed {
+                offset: 3,
+                len: 1
+            }))
+        ));
+        assert_eq!(output, b"abc");
+    }
+
+    #[test]
+    fn test_detect() {
+        let detector = Detector::default();
+        let text = "Pijamalı hasta yağız şoföre çabucak güvendi. ".repeat(400);
+        let (bytes, _, _) = encoding_rs::WINDOWS_1254.encode(&text);
+        let (candidate, reader) = detect(&detector, &bytes[..]).unwrap();
+        let from = candidate.unwrap().encoding;
+        assert_eq!(from, Encoding::Legacy(encoding_rs::WINDOWS_1254));
+        let mut output = Vec::new();
+        Transcoder::new(from, Encoding::Utf8)
+            .transcode(reader, &mut output, |problem| panic!("{problem}"))
+            .unwrap();
+        assert_eq!(output, text.as_bytes());
+    }
+}